@@ -0,0 +1,300 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use yahoo_finance_api::Quote;
+
+use crate::{indicators::Indicator, interval::Interval, json};
+
+/// Number of triggered alerts kept in the history.
+const HISTORY_LENGTH: usize = 500;
+
+/// Conditions are checked between the previous and the latest bar.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    /// The close crosses `level` in either direction.
+    CrossesLevel { level: f64 },
+    /// The close moved at least `percent` from the previous close.
+    PercentMove { percent: f64 },
+    /// The close crosses the first line of `indicator`, or that line crosses
+    /// `level` if one is given.
+    IndicatorCross {
+        indicator: Indicator,
+        level: Option<f64>,
+    },
+    /// The close crosses the extension of a trendline drawn on a chart.
+    CrossesTrendline { start: [f64; 2], end: [f64; 2] },
+}
+
+impl Condition {
+    pub fn describe(&self) -> String {
+        match self {
+            Condition::CrossesLevel { level } => format!("crosses {:.2}", level),
+            Condition::PercentMove { percent } => format!("moves {:.2}%", percent),
+            Condition::IndicatorCross {
+                indicator,
+                level: None,
+            } => format!("crosses {}", indicator.label()),
+            Condition::IndicatorCross {
+                indicator,
+                level: Some(level),
+            } => format!("{} crosses {:.2}", indicator.label(), level),
+            Condition::CrossesTrendline { start, end } => {
+                format!(
+                    "crosses trendline {:.2} → {:.2}",
+                    start[1].min(end[1]),
+                    start[1].max(end[1])
+                )
+            }
+        }
+    }
+
+    /// Describes what happened if the condition holds on the last bar.
+    fn check(&self, quotes: &[Quote]) -> Option<String> {
+        let [.., previous, current] = quotes else {
+            return None;
+        };
+
+        match self {
+            Condition::CrossesLevel { level } => {
+                crossed(previous.close, current.close, *level, *level)
+                    .then(|| format!("Close {:.2} crossed {:.2}", current.close, level))
+            }
+            Condition::PercentMove { percent } => {
+                let change = (current.close / previous.close - 1.0) * 100.0;
+
+                (previous.close != 0.0 && change.abs() >= *percent)
+                    .then(|| format!("Close {:.2} moved {:+.2}%", current.close, change))
+            }
+            Condition::IndicatorCross { indicator, level } => {
+                let series = indicator.compute(quotes).into_iter().next()?;
+                let [.., Some(previous_value), Some(current_value)] = series.values[..] else {
+                    return None;
+                };
+
+                match level {
+                    Some(level) => {
+                        crossed(previous_value, current_value, *level, *level).then(|| {
+                            format!(
+                                "{} {:.2} crossed {:.2}",
+                                indicator.label(),
+                                current_value,
+                                level
+                            )
+                        })
+                    }
+                    None => crossed(previous.close, current.close, previous_value, current_value)
+                        .then(|| {
+                            format!(
+                                "Close {:.2} crossed {} {:.2}",
+                                current.close,
+                                indicator.label(),
+                                current_value
+                            )
+                        }),
+                }
+            }
+            Condition::CrossesTrendline { start, end } => {
+                let line_at = |timestamp: u64| {
+                    let run = end[0] - start[0];
+
+                    if run == 0.0 {
+                        return start[1];
+                    }
+
+                    start[1] + (end[1] - start[1]) * (timestamp as f64 - start[0]) / run
+                };
+
+                let current_value = line_at(current.timestamp);
+
+                crossed(
+                    previous.close,
+                    current.close,
+                    line_at(previous.timestamp),
+                    current_value,
+                )
+                .then(|| {
+                    format!(
+                        "Close {:.2} crossed trendline at {:.2}",
+                        current.close, current_value
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Whether `a` moved from one side of `b` to the other.
+fn crossed(previous_a: f64, current_a: f64, previous_b: f64, current_b: f64) -> bool {
+    (previous_a < previous_b && current_a >= current_b)
+        || (previous_a > previous_b && current_a <= current_b)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: u64,
+    pub symbol: String,
+    pub interval: Interval,
+    pub condition: Condition,
+    pub enabled: bool,
+    /// Disables the alert once it triggered, otherwise it re-arms.
+    #[serde(default)]
+    pub once: bool,
+    /// Seconds of bar time after the triggering bar during which the alert
+    /// stays quiet, one interval skips the next bar.
+    #[serde(default)]
+    pub cooldown: u64,
+    /// Timestamp of the bar that last triggered, each bar triggers only once.
+    pub last_triggered: Option<u64>,
+}
+
+impl Alert {
+    /// Whether a trigger on the bar at `timestamp` is suppressed.
+    fn cooling_down(&self, timestamp: u64) -> bool {
+        self.last_triggered
+            .is_some_and(|last_triggered| timestamp <= last_triggered.saturating_add(self.cooldown))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub alert_id: u64,
+    pub symbol: String,
+    pub interval: Interval,
+    pub message: String,
+    pub bar_timestamp: u64,
+    /// Unix seconds.
+    pub triggered_at: u64,
+}
+
+/// Alert definitions and their history, stored as JSON next to the quote cache.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AlertBook {
+    next_id: u64,
+    pub alerts: Vec<Alert>,
+    pub history: Vec<AlertEvent>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl AlertBook {
+    /// `<data dir>/market-view/alerts.json`, empty if it does not exist yet.
+    pub fn load() -> Self {
+        let Some(path) = json::data_file("alerts.json") else {
+            log::warn!("No data directory found, alerts will not be saved");
+            return AlertBook::default();
+        };
+
+        let mut book: AlertBook = json::load_json(&path, "alerts").unwrap_or_default();
+
+        book.path = Some(path);
+
+        book
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        if let Err(e) = json::save_json_atomic(path, self) {
+            log::error!("Error saving alerts {}: {:#}", path.display(), e);
+        }
+    }
+
+    /// Adds a re-arming alert without cooldown, returns it to set the options.
+    pub fn add(&mut self, symbol: &str, interval: Interval, condition: Condition) -> &mut Alert {
+        self.alerts.push(Alert {
+            id: self.next_id,
+            symbol: symbol.to_string(),
+            interval,
+            condition,
+            enabled: true,
+            once: false,
+            cooldown: 0,
+            last_triggered: None,
+        });
+
+        self.next_id += 1;
+
+        self.alerts.last_mut().unwrap()
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        self.alerts.retain(|alert| alert.id != id);
+    }
+
+    pub fn set_enabled(&mut self, id: u64, enabled: bool) {
+        if let Some(alert) = self.alerts.iter_mut().find(|alert| alert.id == id) {
+            alert.enabled = enabled;
+        }
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Series the enabled alerts are defined on.
+    pub fn watched(&self) -> Vec<(String, Interval)> {
+        let mut watched = self
+            .alerts
+            .iter()
+            .filter(|alert| alert.enabled)
+            .map(|alert| (alert.symbol.clone(), alert.interval))
+            .collect::<Vec<_>>();
+
+        watched.sort();
+        watched.dedup();
+
+        watched
+    }
+
+    /// Checks the enabled alerts of a series against its latest quotes,
+    /// returns whether any triggered.
+    pub fn evaluate(&mut self, symbol: &str, interval: Interval, quotes: &[Quote]) -> bool {
+        let Some(last) = quotes.last() else {
+            return false;
+        };
+
+        let triggered_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut triggered = false;
+
+        for alert in self.alerts.iter_mut().filter(|alert| {
+            alert.enabled
+                && alert.symbol == symbol
+                && alert.interval == interval
+                && !alert.cooling_down(last.timestamp)
+        }) {
+            let Some(message) = alert.condition.check(quotes) else {
+                continue;
+            };
+
+            log::info!("Alert on {} {}: {}", symbol, interval, message);
+
+            alert.last_triggered = Some(last.timestamp);
+            alert.enabled &= !alert.once;
+
+            self.history.push(AlertEvent {
+                alert_id: alert.id,
+                symbol: symbol.to_string(),
+                interval,
+                message,
+                bar_timestamp: last.timestamp,
+                triggered_at,
+            });
+
+            triggered = true;
+        }
+
+        if triggered {
+            let excess = self.history.len().saturating_sub(HISTORY_LENGTH);
+
+            self.history.drain(..excess);
+        }
+
+        triggered
+    }
+}
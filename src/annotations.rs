@@ -0,0 +1,138 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use market_view::cache::encode_symbol;
+
+use crate::windows::Drawing;
+
+#[derive(Default)]
+struct AnnotationsInner {
+    drawings: BTreeMap<String, Vec<Drawing>>,
+    /// Bumped on every change of a symbol's drawings, so charts know when to reload.
+    revisions: HashMap<String, u64>,
+    next_revision: u64,
+}
+
+/// Drawings per symbol, shared by all charts and persisted through eframe's
+/// storage, so they show up on every timeframe and after a restart.
+#[derive(Clone, Default)]
+pub struct Annotations {
+    inner: Arc<Mutex<AnnotationsInner>>,
+}
+
+/// The file format of exported drawings.
+#[derive(Serialize, Deserialize)]
+struct ExportedDrawings {
+    symbol: String,
+    drawings: Vec<Drawing>,
+}
+
+impl Annotations {
+    pub const STORAGE_KEY: &'static str = "annotations";
+
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let drawings = storage
+            .and_then(|storage| eframe::get_value(storage, Self::STORAGE_KEY))
+            .unwrap_or_default();
+
+        Annotations {
+            inner: Arc::new(Mutex::new(AnnotationsInner {
+                drawings,
+                ..Default::default()
+            })),
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(
+            storage,
+            Self::STORAGE_KEY,
+            &self.inner.lock().unwrap().drawings,
+        );
+    }
+
+    /// The drawings of `symbol` with their revision.
+    pub fn drawings(&self, symbol: &str) -> (u64, Vec<Drawing>) {
+        let inner = self.inner.lock().unwrap();
+
+        (
+            inner.revisions.get(symbol).copied().unwrap_or_default(),
+            inner.drawings.get(symbol).cloned().unwrap_or_default(),
+        )
+    }
+
+    pub fn revision(&self, symbol: &str) -> u64 {
+        let inner = self.inner.lock().unwrap();
+
+        inner.revisions.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Replaces the drawings of `symbol`, returns their new revision.
+    pub fn set(&self, symbol: &str, drawings: Vec<Drawing>) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+
+        match drawings.is_empty() {
+            true => inner.drawings.remove(symbol),
+            false => inner.drawings.insert(symbol.to_string(), drawings),
+        };
+
+        inner.next_revision += 1;
+
+        let revision = inner.next_revision;
+
+        inner.revisions.insert(symbol.to_string(), revision);
+
+        revision
+    }
+}
+
+/// Writes the drawings of `symbol` to a JSON file.
+pub fn export(path: &Path, symbol: &str, drawings: &[Drawing]) -> anyhow::Result<()> {
+    if let Some(directory) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Error creating {}", directory.display()))?;
+    }
+
+    let exported = ExportedDrawings {
+        symbol: symbol.to_string(),
+        drawings: drawings.to_vec(),
+    };
+
+    std::fs::write(path, serde_json::to_vec_pretty(&exported)?)
+        .with_context(|| format!("Error writing {}", path.display()))
+}
+
+/// Where the drawings of `symbol` are exported by default,
+/// `<data dir>/market-view/drawings/<symbol>.json` or the current directory
+/// if the platform has no data dir.
+pub fn export_path(symbol: &str) -> PathBuf {
+    let file_name = format!("{}.json", encode_symbol(symbol));
+
+    match dirs::data_dir() {
+        Some(directory) => directory
+            .join("market-view")
+            .join("drawings")
+            .join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// Reads drawings exported with [`export`], whatever symbol they were drawn on.
+pub fn import(path: &Path) -> anyhow::Result<Vec<Drawing>> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Error reading {}", path.display()))?;
+
+    let exported: ExportedDrawings = serde_json::from_reader(std::io::BufReader::new(file))
+        .with_context(|| format!("Invalid drawings in {}", path.display()))?;
+
+    Ok(exported.drawings)
+}
@@ -1,51 +1,196 @@
 use std::sync::Arc;
-use std::sync::LazyLock;
 use std::sync::Mutex;
 
 use egui::Vec2;
 
-use yahoo_finance_api::YahooConnector;
+use market_view::portfolio::Ledger;
+use market_view::requests::RequestLog;
+use market_view::scheduler::Scheduler;
+use market_view::{Interval, Storage};
 
-use crate::storage::Storage;
+use crate::annotations::Annotations;
+use crate::windows::AlertsWindow;
+use crate::windows::NotificationsPanel;
 use crate::windows::PlotWindow;
+use crate::windows::PortfolioWindow;
+use crate::windows::RequestsWindow;
+use crate::windows::SchedulerWindow;
 use crate::windows::SearchWindow;
 use crate::windows::ViewWindow;
-
-pub static CONNECTOR: LazyLock<YahooConnector> = LazyLock::new(|| YahooConnector::new());
-
-pub static STORAGE: LazyLock<Storage> = LazyLock::new(|| Storage::default());
-
-pub static RUNTIME: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
-    tokio::runtime::Builder::new_multi_thread()
-        .worker_threads(4)
-        .enable_all()
-        .build()
-        .unwrap()
-});
+use crate::windows::{WatchlistWindow, Watchlists};
+use crate::workspace::{Workspace, Workspaces, DEFAULT_WORKSPACE};
 
 pub struct App {
+    storage: Storage,
     search_window: SearchWindow,
+    watchlist_window: WatchlistWindow,
+    portfolio_window: PortfolioWindow,
+    alerts_window: AlertsWindow,
+    scheduler_window: SchedulerWindow,
+    requests_window: RequestsWindow,
+    notifications: NotificationsPanel,
     plot_windows: Arc<Mutex<Vec<PlotWindow>>>,
+    annotations: Annotations,
+    workspaces: Workspaces,
+    new_workspace_name: String,
 }
 
 impl App {
-    pub fn new(_cc: &eframe::CreationContext) -> Self {
-        App::default()
+    pub fn new(cc: &eframe::CreationContext, storage: Storage, requests: RequestLog) -> Self {
+        let plot_windows = Arc::new(Mutex::new(Vec::new()));
+        let annotations = Annotations::load(cc.storage);
+
+        storage.update_quotes_checked("NIO", Interval::OneDay);
+
+        let mut app = App {
+            search_window: SearchWindow::new(
+                storage.clone(),
+                plot_windows.clone(),
+                annotations.clone(),
+            ),
+            watchlist_window: WatchlistWindow::new(
+                storage.clone(),
+                Watchlists::load(cc.storage),
+                plot_windows.clone(),
+                annotations.clone(),
+            ),
+            portfolio_window: PortfolioWindow::new(storage.clone(), Ledger::load()),
+            alerts_window: AlertsWindow::new(storage.clone()),
+            requests_window: RequestsWindow::new(requests),
+            notifications: NotificationsPanel::new(&storage),
+            scheduler_window: SchedulerWindow::new(
+                Scheduler::new(storage.clone(), Default::default()),
+                cc.storage,
+            ),
+            storage,
+            plot_windows,
+            annotations,
+            workspaces: Workspaces::load(cc.storage),
+            new_workspace_name: String::new(),
+        };
+
+        app.open_workspace(app.workspaces.current());
+
+        app
     }
-}
 
-impl Default for App {
-    fn default() -> Self {
-        let plot_windows = Arc::new(Mutex::new(Vec::new()));
+    /// Stores the open charts in the current workspace.
+    fn store_workspace(&mut self) {
+        let charts = self
+            .plot_windows
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|plot_window| !plot_window.is_request_close())
+            .map(PlotWindow::state)
+            .collect();
 
-        let search_window = SearchWindow::new(plot_windows.clone());
+        self.workspaces
+            .saved
+            .insert(self.workspaces.current.clone(), Workspace { charts });
+    }
 
-        STORAGE.update_quotes_checked("NIO");
+    /// Replaces the open charts with the ones of `workspace`.
+    fn open_workspace(&mut self, workspace: Workspace) {
+        *self.plot_windows.lock().unwrap() = workspace
+            .charts
+            .into_iter()
+            .map(|state| {
+                PlotWindow::from_state(self.storage.clone(), self.annotations.clone(), state)
+            })
+            .collect();
+    }
 
-        App {
-            search_window,
-            plot_windows,
+    fn switch_workspace(&mut self, name: String) {
+        if name == self.workspaces.current {
+            return;
         }
+
+        self.store_workspace();
+        self.workspaces.current = name;
+        self.open_workspace(self.workspaces.current());
+    }
+
+    fn workspace_controls(&mut self, ui: &mut egui::Ui) {
+        let mut selected = self.workspaces.current.clone();
+
+        egui::ComboBox::from_id_source("Workspace")
+            .selected_text(&selected)
+            .show_ui(ui, |ui| {
+                for name in self.workspaces.names() {
+                    ui.selectable_value(&mut selected, name.clone(), name);
+                }
+            });
+
+        self.switch_workspace(selected);
+
+        ui.add(
+            egui::TextEdit::singleline(&mut self.new_workspace_name)
+                .hint_text("Workspace name")
+                .desired_width(120.0),
+        );
+
+        let name = self.new_workspace_name.trim().to_string();
+
+        ui.add_enabled(!name.is_empty(), egui::Button::new("Save as"))
+            .on_hover_text("Save the open charts as a new workspace")
+            .clicked()
+            .then(|| {
+                self.workspaces.current = name;
+                self.store_workspace();
+                self.new_workspace_name.clear();
+            });
+
+        ui.add_enabled(
+            self.workspaces.current != DEFAULT_WORKSPACE,
+            egui::Button::new("Delete"),
+        )
+        .on_hover_text("Delete the current workspace")
+        .clicked()
+        .then(|| {
+            self.workspaces.saved.remove(&self.workspaces.current);
+            self.workspaces.current = DEFAULT_WORKSPACE.to_string();
+            self.open_workspace(self.workspaces.current());
+        });
+    }
+
+    /// Keeps the series of open charts, the current watchlist, the portfolio
+    /// and the alerts fresh.
+    fn update_refresh_targets(&self) {
+        let charts = self
+            .plot_windows
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|plot_window| {
+                let (symbol, interval) = plot_window.series();
+
+                (symbol.to_string(), interval)
+            })
+            .collect::<Vec<_>>();
+
+        let watchlist = self
+            .watchlist_window
+            .watchlists
+            .symbols()
+            .iter()
+            .map(|symbol| (symbol.clone(), Interval::OneDay));
+
+        let portfolio = self
+            .portfolio_window
+            .symbols()
+            .into_iter()
+            .map(|symbol| (symbol, Interval::OneDay));
+
+        let alerts = self.storage.alerts().watched();
+
+        self.scheduler_window.set_targets(
+            charts
+                .into_iter()
+                .chain(watchlist)
+                .chain(portfolio)
+                .chain(alerts),
+        );
     }
 }
 
@@ -64,7 +209,7 @@ impl eframe::App for App {
                             .on_hover_text("Close the application")
                             .clicked()
                             .then(|| {
-                                std::process::exit(0);
+                                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                             });
                     },
                 );
@@ -74,13 +219,27 @@ impl eframe::App for App {
                     egui::Layout::left_to_right(egui::Align::Center),
                     |ui| {
                         ui.label("Market View");
+
+                        ui.separator();
+
+                        self.workspace_controls(ui);
                     },
                 );
             });
         });
 
+        self.alerts_window.log_panel(ctx);
+        self.notifications.show(ctx);
+
+        self.update_refresh_targets();
+
         egui::CentralPanel::default().show(ctx, |ui| {
             self.search_window.view(ui);
+            self.watchlist_window.view(ui);
+            self.portfolio_window.view(ui);
+            self.alerts_window.view(ui);
+            self.scheduler_window.view(ui);
+            self.requests_window.view(ui);
 
             let mut window_id_to_remove = None;
 
@@ -100,4 +259,12 @@ impl eframe::App for App {
             }
         });
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.store_workspace();
+        self.workspaces.save(storage);
+        self.watchlist_window.watchlists.save(storage);
+        self.scheduler_window.save(storage);
+        self.annotations.save(storage);
+    }
 }
@@ -0,0 +1,440 @@
+use std::collections::VecDeque;
+
+use yahoo_finance_api::Quote;
+
+use crate::indicators;
+
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Quantities are in shares, sells never exceed the held position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Order {
+    Buy(f64),
+    Sell(f64),
+}
+
+/// What a strategy sees when a bar closes.
+pub struct BarContext<'a> {
+    /// All bars up to and including the current one.
+    pub quotes: &'a [Quote],
+    pub position: f64,
+    pub cash: f64,
+}
+
+impl BarContext<'_> {
+    pub fn bar(&self) -> &Quote {
+        self.quotes.last().unwrap()
+    }
+
+    /// Whole shares the cash buys at the current close.
+    pub fn affordable(&self) -> f64 {
+        let close = self.bar().close;
+
+        if close > 0.0 {
+            (self.cash / close).floor()
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Trading rule replayed bar by bar.
+///
+/// Orders returned for a bar are filled at the open of the next bar.
+pub trait Strategy {
+    /// Called with all bars before the replay to precompute indicators, whose
+    /// value at a bar must only depend on the bars up to it.
+    fn prepare(&mut self, _quotes: &[Quote]) {}
+
+    fn on_bar(&mut self, context: &BarContext) -> Vec<Order>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Commission {
+    pub per_order: f64,
+    /// Fraction of the traded value.
+    pub rate: f64,
+}
+
+impl Default for Commission {
+    fn default() -> Self {
+        Commission {
+            per_order: 1.0,
+            rate: 0.0,
+        }
+    }
+}
+
+impl Commission {
+    fn cost(&self, value: f64) -> f64 {
+        self.per_order + self.rate * value.abs()
+    }
+}
+
+/// Moves fill prices against the trader.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Slippage {
+    /// Fraction of the price.
+    Relative(f64),
+    /// Absolute amount per share.
+    Fixed(f64),
+}
+
+impl Default for Slippage {
+    fn default() -> Self {
+        Slippage::Relative(0.0005)
+    }
+}
+
+impl Slippage {
+    fn apply(&self, price: f64, order: Order) -> f64 {
+        let amount = match *self {
+            Slippage::Relative(fraction) => price * fraction,
+            Slippage::Fixed(amount) => amount,
+        };
+
+        match order {
+            Order::Buy(_) => price + amount,
+            Order::Sell(_) => (price - amount).max(0.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BacktestConfig {
+    pub initial_cash: f64,
+    pub commission: Commission,
+    pub slippage: Slippage,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        BacktestConfig {
+            initial_cash: 10_000.0,
+            commission: Commission::default(),
+            slippage: Slippage::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fill {
+    pub timestamp: u64,
+    pub order: Order,
+    pub price: f64,
+    pub commission: f64,
+}
+
+/// A closed round trip, matched first in first out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trade {
+    pub entry_timestamp: u64,
+    pub entry_price: f64,
+    pub exit_timestamp: u64,
+    pub exit_price: f64,
+    pub quantity: f64,
+    /// After commissions.
+    pub profit: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Statistics {
+    pub total_return: f64,
+    pub cagr: f64,
+    /// Annualized, with a risk free rate of zero.
+    pub sharpe: f64,
+    /// Largest fall from a peak as a fraction of the peak.
+    pub max_drawdown: f64,
+    pub win_rate: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BacktestReport {
+    /// Timestamp and equity at each close.
+    pub equity: Vec<[f64; 2]>,
+    pub fills: Vec<Fill>,
+    pub trades: Vec<Trade>,
+    pub statistics: Statistics,
+}
+
+/// Replays `quotes` through `strategy`.
+pub fn run(
+    quotes: &[Quote],
+    strategy: &mut dyn Strategy,
+    config: &BacktestConfig,
+) -> BacktestReport {
+    let mut cash = config.initial_cash;
+    let mut position = 0.0;
+    let mut pending: Vec<Order> = Vec::new();
+    let mut report = BacktestReport::default();
+
+    strategy.prepare(quotes);
+
+    for (index, quote) in quotes.iter().enumerate() {
+        for order in pending.drain(..) {
+            let price = config.slippage.apply(quote.open, order);
+
+            let quantity = match order {
+                Order::Buy(quantity) => {
+                    let affordable = (cash - config.commission.per_order)
+                        / (price * (1.0 + config.commission.rate));
+
+                    quantity.min(affordable.floor())
+                }
+                Order::Sell(quantity) => quantity.min(position),
+            };
+
+            if quantity <= 0.0 {
+                continue;
+            }
+
+            let commission = config.commission.cost(quantity * price);
+
+            let order = match order {
+                Order::Buy(_) => {
+                    cash -= quantity * price + commission;
+                    position += quantity;
+
+                    Order::Buy(quantity)
+                }
+                Order::Sell(_) => {
+                    cash += quantity * price - commission;
+                    position -= quantity;
+
+                    Order::Sell(quantity)
+                }
+            };
+
+            report.fills.push(Fill {
+                timestamp: quote.timestamp,
+                order,
+                price,
+                commission,
+            });
+        }
+
+        report
+            .equity
+            .push([quote.timestamp as f64, cash + position * quote.close]);
+
+        pending = strategy.on_bar(&BarContext {
+            quotes: &quotes[..=index],
+            position,
+            cash,
+        });
+    }
+
+    report.trades = match_trades(&report.fills);
+    report.statistics = statistics(&report.equity, &report.trades);
+
+    report
+}
+
+fn match_trades(fills: &[Fill]) -> Vec<Trade> {
+    // Entry time, quantity, price and commission per share of the open lots.
+    let mut lots: VecDeque<(u64, f64, f64, f64)> = VecDeque::new();
+    let mut trades = Vec::new();
+
+    for fill in fills {
+        match fill.order {
+            Order::Buy(quantity) => {
+                lots.push_back((
+                    fill.timestamp,
+                    quantity,
+                    fill.price,
+                    fill.commission / quantity,
+                ));
+            }
+            Order::Sell(mut quantity) => {
+                let exit_commission = fill.commission / quantity;
+
+                while quantity > 0.0 {
+                    let Some((timestamp, lot_quantity, price, entry_commission)) = lots.front_mut()
+                    else {
+                        break;
+                    };
+
+                    let taken = quantity.min(*lot_quantity);
+
+                    trades.push(Trade {
+                        entry_timestamp: *timestamp,
+                        entry_price: *price,
+                        exit_timestamp: fill.timestamp,
+                        exit_price: fill.price,
+                        quantity: taken,
+                        profit: taken * (fill.price - *price - *entry_commission - exit_commission),
+                    });
+
+                    quantity -= taken;
+                    *lot_quantity -= taken;
+
+                    if *lot_quantity <= f64::EPSILON {
+                        lots.pop_front();
+                    }
+                }
+            }
+        }
+    }
+
+    trades
+}
+
+fn statistics(equity: &[[f64; 2]], trades: &[Trade]) -> Statistics {
+    let (Some(first), Some(last)) = (equity.first(), equity.last()) else {
+        return Statistics::default();
+    };
+
+    let total_return = last[1] / first[1] - 1.0;
+    let years = (last[0] - first[0]) / SECONDS_PER_YEAR;
+
+    let cagr = if years > 0.0 && last[1] > 0.0 {
+        (last[1] / first[1]).powf(1.0 / years) - 1.0
+    } else {
+        0.0
+    };
+
+    let returns = equity
+        .windows(2)
+        .filter(|pair| pair[0][1] != 0.0)
+        .map(|pair| pair[1][1] / pair[0][1] - 1.0)
+        .collect::<Vec<_>>();
+
+    let sharpe = if returns.len() > 1 && years > 0.0 {
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let variance =
+            returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+        let periods_per_year = returns.len() as f64 / years;
+
+        if variance > 0.0 {
+            mean / variance.sqrt() * periods_per_year.sqrt()
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    };
+
+    let mut peak = f64::MIN;
+    let mut max_drawdown = 0.0_f64;
+
+    for [_, value] in equity {
+        peak = peak.max(*value);
+
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max((peak - value) / peak);
+        }
+    }
+
+    let win_rate = if trades.is_empty() {
+        0.0
+    } else {
+        trades.iter().filter(|trade| trade.profit > 0.0).count() as f64 / trades.len() as f64
+    };
+
+    Statistics {
+        total_return,
+        cagr,
+        sharpe,
+        max_drawdown,
+        win_rate,
+    }
+}
+
+/// Goes all in when the fast SMA crosses above the slow one and exits on
+/// the opposite cross.
+pub struct SmaCrossover {
+    pub fast: usize,
+    pub slow: usize,
+    previous: Option<(f64, f64)>,
+}
+
+impl SmaCrossover {
+    pub fn new(fast: usize, slow: usize) -> Self {
+        SmaCrossover {
+            fast: fast.max(1),
+            slow: slow.max(1),
+            previous: None,
+        }
+    }
+
+    fn average(quotes: &[Quote], period: usize) -> Option<f64> {
+        let window = quotes.get(quotes.len().checked_sub(period)?..)?;
+
+        Some(window.iter().map(|quote| quote.close).sum::<f64>() / period as f64)
+    }
+}
+
+impl Strategy for SmaCrossover {
+    fn on_bar(&mut self, context: &BarContext) -> Vec<Order> {
+        let (Some(fast), Some(slow)) = (
+            Self::average(context.quotes, self.fast),
+            Self::average(context.quotes, self.slow),
+        ) else {
+            return Vec::new();
+        };
+
+        let previous = self.previous.replace((fast, slow));
+
+        match previous {
+            Some((previous_fast, previous_slow)) => {
+                if previous_fast <= previous_slow && fast > slow && context.position == 0.0 {
+                    vec![Order::Buy(context.affordable())]
+                } else if previous_fast >= previous_slow && fast < slow && context.position > 0.0 {
+                    vec![Order::Sell(context.position)]
+                } else {
+                    Vec::new()
+                }
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Buys when the RSI drops below `oversold` and sells above `overbought`.
+pub struct RsiReversion {
+    pub period: usize,
+    pub oversold: f64,
+    pub overbought: f64,
+    /// RSI of every bar seen so far.
+    values: Vec<Option<f64>>,
+}
+
+impl RsiReversion {
+    pub fn new(period: usize, oversold: f64, overbought: f64) -> Self {
+        RsiReversion {
+            period: period.max(1),
+            oversold,
+            overbought,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl Strategy for RsiReversion {
+    fn prepare(&mut self, quotes: &[Quote]) {
+        let closes = quotes
+            .iter()
+            .map(|quote| Some(quote.close))
+            .collect::<Vec<_>>();
+
+        self.values = indicators::rsi(&closes, self.period);
+    }
+
+    fn on_bar(&mut self, context: &BarContext) -> Vec<Order> {
+        if self.values.len() < context.quotes.len() {
+            self.prepare(context.quotes);
+        }
+
+        let Some(Some(rsi)) = self.values.get(context.quotes.len() - 1).copied() else {
+            return Vec::new();
+        };
+
+        if rsi < self.oversold && context.position == 0.0 {
+            vec![Order::Buy(context.affordable())]
+        } else if rsi > self.overbought && context.position > 0.0 {
+            vec![Order::Sell(context.position)]
+        } else {
+            Vec::new()
+        }
+    }
+}
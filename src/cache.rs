@@ -0,0 +1,219 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use yahoo_finance_api::Quote;
+
+use crate::interval::Interval;
+
+const MAGIC: &[u8; 4] = b"MVQ3";
+
+/// Bytes per bar: timestamp, five prices and volume.
+const BAR_SIZE: u64 = 7 * 8;
+
+/// A cached series as read back from disk.
+pub struct CachedQuotes {
+    pub symbol: String,
+    pub interval: Interval,
+    /// Offset of the exchange's time zone from UTC in seconds.
+    pub utc_offset: i32,
+    pub last_update: SystemTime,
+    pub quotes: Vec<Quote>,
+}
+
+/// Keeps one compact binary file per symbol and interval.
+///
+/// Layout (little endian): magic, symbol length (u16), symbol, interval
+/// length (u8), interval, UTC offset in seconds (i32), last update in unix
+/// seconds (u64), bar count (u64), then per bar the timestamp (u64), open,
+/// high, low, close, adjclose (f64) and volume (u64).
+#[derive(Clone, Debug)]
+pub struct QuotesCache {
+    directory: PathBuf,
+}
+
+impl QuotesCache {
+    pub fn new(directory: PathBuf) -> Self {
+        QuotesCache { directory }
+    }
+
+    /// `<data dir>/market-view/quotes`, if the platform has a data dir.
+    pub fn in_data_dir() -> Option<Self> {
+        let directory = dirs::data_dir()?.join("market-view").join("quotes");
+
+        Some(QuotesCache::new(directory))
+    }
+
+    pub fn load_all(&self) -> Vec<CachedQuotes> {
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::debug!("No quote cache at {}: {}", self.directory.display(), e);
+                return Vec::new();
+            }
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "bin"))
+            .filter_map(|path| match Self::read(&path) {
+                Ok(cached) => Some(cached),
+                Err(e) => {
+                    log::error!("Error reading {}: {:#}", path.display(), e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn save(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        utc_offset: i32,
+        last_update: SystemTime,
+        quotes: &[Quote],
+    ) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.directory)
+            .with_context(|| format!("Error creating {}", self.directory.display()))?;
+
+        let path = self.path(symbol, interval);
+        let temporary = path.with_extension("tmp");
+
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+
+        let last_update = last_update
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(symbol.len() as u16).to_le_bytes())?;
+        writer.write_all(symbol.as_bytes())?;
+        writer.write_all(&[interval.as_str().len() as u8])?;
+        writer.write_all(interval.as_str().as_bytes())?;
+        writer.write_all(&utc_offset.to_le_bytes())?;
+        writer.write_all(&last_update.to_le_bytes())?;
+        writer.write_all(&(quotes.len() as u64).to_le_bytes())?;
+
+        for quote in quotes {
+            writer.write_all(&quote.timestamp.to_le_bytes())?;
+
+            for value in [
+                quote.open,
+                quote.high,
+                quote.low,
+                quote.close,
+                quote.adjclose,
+            ] {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+
+            writer.write_all(&quote.volume.to_le_bytes())?;
+        }
+
+        writer.into_inner()?.sync_all()?;
+
+        std::fs::rename(&temporary, &path)
+            .with_context(|| format!("Error writing {}", path.display()))
+    }
+
+    /// Symbols can contain characters like `/`, so bytes outside a safe
+    /// subset are percent-encoded. Distinct symbols get distinct names and
+    /// the `_` before the interval stays unambiguous.
+    fn path(&self, symbol: &str, interval: Interval) -> PathBuf {
+        self.directory
+            .join(format!("{}_{}.bin", encode_symbol(symbol), interval))
+    }
+
+    fn read(path: &Path) -> anyhow::Result<CachedQuotes> {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        anyhow::ensure!(&magic == MAGIC, "not a quote cache file");
+
+        let symbol_length = u16::from_le_bytes(read_bytes(&mut reader)?) as usize;
+
+        let mut symbol = vec![0; symbol_length];
+        reader.read_exact(&mut symbol)?;
+
+        let symbol = String::from_utf8(symbol)?;
+
+        let [interval_length] = read_bytes(&mut reader)?;
+
+        let mut interval = vec![0; interval_length as usize];
+        reader.read_exact(&mut interval)?;
+
+        let interval = String::from_utf8(interval)?.parse()?;
+        let utc_offset = i32::from_le_bytes(read_bytes(&mut reader)?);
+        let last_update = u64::from_le_bytes(read_bytes(&mut reader)?);
+        let count = u64::from_le_bytes(read_bytes(&mut reader)?);
+
+        let header =
+            (MAGIC.len() + 2 + symbol_length + 1 + interval_length as usize + 4 + 8 + 8) as u64;
+        let room = length.saturating_sub(header) / BAR_SIZE;
+
+        anyhow::ensure!(
+            count <= room,
+            "truncated, {} bars announced but room for {}",
+            count,
+            room
+        );
+
+        let mut quotes = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let timestamp = u64::from_le_bytes(read_bytes(&mut reader)?);
+            let [open, high, low, close, adjclose] =
+                [(); 5].map(|_| read_bytes(&mut reader).map(f64::from_le_bytes));
+
+            quotes.push(Quote {
+                timestamp,
+                open: open?,
+                high: high?,
+                low: low?,
+                close: close?,
+                adjclose: adjclose?,
+                volume: u64::from_le_bytes(read_bytes(&mut reader)?),
+            });
+        }
+
+        Ok(CachedQuotes {
+            symbol,
+            interval,
+            utc_offset,
+            last_update: UNIX_EPOCH + Duration::from_secs(last_update),
+            quotes,
+        })
+    }
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> std::io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// `symbol` as a file name, bytes other than letters, digits and `-.=^` are
+/// percent-encoded so distinct symbols never share a file.
+pub fn encode_symbol(symbol: &str) -> String {
+    symbol
+        .bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'=' | b'^' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
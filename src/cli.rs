@@ -0,0 +1,362 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use yahoo_finance_api::Quote;
+
+use market_view::{indicators::Indicator, requests, Interval, Storage};
+
+/// Market data viewer, starts the desktop app unless a subcommand is given.
+#[derive(Parser)]
+#[command(
+    name = "market-view",
+    version,
+    about,
+    after_help = "Environment:\n  \
+        MARKET_VIEW_PROVIDER     yahoo (default), fake or csv\n  \
+        MARKET_VIEW_DATA_DIR     directory with one <SYMBOL>.csv file of daily bars per symbol,\n                           \
+        only CSV is read, convert Parquet files to CSV first\n  \
+        MARKET_VIEW_CSV_COLUMNS  header names of the CSV columns, e.g. timestamp=Date"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Search symbols by name
+    Search {
+        query: String,
+        #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+    },
+    /// Update the cached bars of a symbol and print them
+    History {
+        symbol: String,
+        #[command(flatten)]
+        range: Range,
+        #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+    },
+    /// Write the bars of a symbol to a file
+    Export {
+        symbol: String,
+        /// Format from the extension (.csv or .json) unless given
+        output: PathBuf,
+        #[command(flatten)]
+        range: Range,
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
+        /// Only use the cache, do not fetch from the provider
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Print indicator values, e.g. `indicators AAPL sma:50 rsi:14 macd`
+    Indicators {
+        symbol: String,
+        #[arg(required = true)]
+        indicators: Vec<Indicator>,
+        #[command(flatten)]
+        range: Range,
+        #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+    },
+}
+
+#[derive(clap::Args)]
+pub struct Range {
+    #[arg(short, long, default_value_t = Interval::OneDay)]
+    interval: Interval,
+    /// First date to print, YYYY-MM-DD
+    #[arg(long)]
+    from: Option<chrono::NaiveDate>,
+    /// Last date to print, YYYY-MM-DD
+    #[arg(long)]
+    to: Option<chrono::NaiveDate>,
+}
+
+impl Range {
+    fn contains(&self, quote: &Quote) -> bool {
+        let Some(date) = chrono::DateTime::from_timestamp(quote.timestamp as i64, 0) else {
+            return false;
+        };
+
+        let date = date.date_naive();
+
+        self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> anyhow::Result<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => Ok(Format::Csv),
+            Some(extension) if extension.eq_ignore_ascii_case("json") => Ok(Format::Json),
+            _ => anyhow::bail!(
+                "Cannot tell the format of {}, pass --format",
+                path.display()
+            ),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Bar {
+    timestamp: u64,
+    date: String,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    adjclose: f64,
+    volume: u64,
+}
+
+impl Bar {
+    fn new(quote: &Quote, interval: Interval) -> Self {
+        Bar {
+            timestamp: quote.timestamp,
+            date: format_date(quote.timestamp, interval),
+            open: quote.open,
+            high: quote.high,
+            low: quote.low,
+            close: quote.close,
+            adjclose: quote.adjclose,
+            volume: quote.volume,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SearchResult<'a> {
+    symbol: &'a str,
+    name: &'a str,
+    exchange: &'a str,
+    quote_type: &'a str,
+}
+
+pub fn run(storage: &Storage, command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Search { query, format } => {
+            let results = storage
+                .runtime()
+                .block_on(requests::search(storage.provider().as_ref(), &query))?;
+
+            let results = results
+                .iter()
+                .map(|result| SearchResult {
+                    symbol: &result.symbol,
+                    name: &result.long_name,
+                    exchange: &result.exchange,
+                    quote_type: &result.quote_type,
+                })
+                .collect::<Vec<_>>();
+
+            write_records(std::io::stdout().lock(), format, &results)
+        }
+        Command::History {
+            symbol,
+            range,
+            format,
+        } => {
+            let bars = bars(storage, &symbol, &range, true)?;
+
+            write_records(std::io::stdout().lock(), format, &bars)
+        }
+        Command::Export {
+            symbol,
+            output,
+            range,
+            format,
+            offline,
+        } => {
+            let format = match format {
+                Some(format) => format,
+                None => Format::from_path(&output)?,
+            };
+
+            let bars = bars(storage, &symbol, &range, !offline)?;
+
+            let file = std::fs::File::create(&output)
+                .with_context(|| format!("Cannot create {}", output.display()))?;
+
+            write_records(std::io::BufWriter::new(file), format, &bars)?;
+
+            log::info!("Wrote {} bars to {}", bars.len(), output.display());
+
+            Ok(())
+        }
+        Command::Indicators {
+            symbol,
+            indicators,
+            range,
+            format,
+        } => {
+            let quotes = quotes(storage, &symbol, range.interval, true)?;
+
+            let columns = indicators
+                .iter()
+                .flat_map(|indicator| {
+                    indicator.compute(&quotes).into_iter().map(move |series| {
+                        (format!("{} {}", indicator.label(), series.name), series)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let rows = quotes
+                .iter()
+                .enumerate()
+                .filter(|(_, quote)| range.contains(quote))
+                .map(|(index, quote)| {
+                    (
+                        quote,
+                        columns
+                            .iter()
+                            .map(|(_, series)| series.values[index])
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let mut stdout = std::io::stdout().lock();
+
+            match format {
+                Format::Csv => {
+                    let mut csv = csv::Writer::from_writer(stdout);
+
+                    csv.write_record(
+                        ["timestamp", "date", "close"]
+                            .into_iter()
+                            .chain(columns.iter().map(|(name, _)| name.as_str())),
+                    )?;
+
+                    for (quote, values) in rows {
+                        csv.write_record(
+                            [
+                                quote.timestamp.to_string(),
+                                format_date(quote.timestamp, range.interval),
+                                quote.close.to_string(),
+                            ]
+                            .into_iter()
+                            .chain(values.iter().map(|value| {
+                                value.map(|value| value.to_string()).unwrap_or_default()
+                            })),
+                        )?;
+                    }
+
+                    csv.flush()?;
+                }
+                Format::Json => {
+                    let rows = rows
+                        .into_iter()
+                        .map(|(quote, values)| {
+                            let mut row = serde_json::Map::new();
+
+                            row.insert("timestamp".into(), quote.timestamp.into());
+                            row.insert(
+                                "date".into(),
+                                format_date(quote.timestamp, range.interval).into(),
+                            );
+                            row.insert("close".into(), quote.close.into());
+
+                            for ((name, _), value) in columns.iter().zip(values) {
+                                row.insert(name.clone(), value.into());
+                            }
+
+                            row
+                        })
+                        .collect::<Vec<_>>();
+
+                    serde_json::to_writer_pretty(&mut stdout, &rows)?;
+                    writeln!(stdout)?;
+                }
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Quotes from the shared cache, updated through the provider if `fetch`.
+fn quotes(
+    storage: &Storage,
+    symbol: &str,
+    interval: Interval,
+    fetch: bool,
+) -> anyhow::Result<Vec<Quote>> {
+    let mut error = None;
+
+    if fetch {
+        let (source, _) = interval.source();
+
+        error = storage
+            .runtime()
+            .block_on(storage.update(symbol, source, source.update_interval()))
+            .err();
+    }
+
+    let quotes = storage
+        .snapshot()
+        .series(symbol, interval)
+        .filter(|quotes| !quotes.is_empty());
+
+    match (quotes, error) {
+        (Some(quotes), Some(error)) => {
+            log::warn!("Using cached quotes for {}: {}", symbol, error);
+            Ok(quotes)
+        }
+        (Some(quotes), None) => Ok(quotes),
+        (None, Some(error)) => Err(error).with_context(|| format!("Loading {}", symbol)),
+        (None, None) => anyhow::bail!("No {} quotes for {}", interval, symbol),
+    }
+}
+
+fn bars(storage: &Storage, symbol: &str, range: &Range, fetch: bool) -> anyhow::Result<Vec<Bar>> {
+    Ok(quotes(storage, symbol, range.interval, fetch)?
+        .iter()
+        .filter(|quote| range.contains(quote))
+        .map(|quote| Bar::new(quote, range.interval))
+        .collect())
+}
+
+fn write_records<T: Serialize>(
+    mut writer: impl Write,
+    format: Format,
+    records: &[T],
+) -> anyhow::Result<()> {
+    match format {
+        Format::Csv => {
+            let mut csv = csv::Writer::from_writer(writer);
+
+            for record in records {
+                csv.serialize(record)?;
+            }
+
+            csv.flush()?;
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut writer, records)?;
+            writeln!(writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn format_date(timestamp: u64, interval: Interval) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .format(interval.date_format())
+        .to_string()
+}
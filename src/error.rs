@@ -0,0 +1,144 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use yahoo_finance_api::YahooError;
+
+/// Number of errors kept in an [`ErrorLog`].
+const LOG_LENGTH: usize = 100;
+
+/// Why market data could not be loaded, as shown to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataError {
+    /// The provider could not be reached, did not answer in time or had a
+    /// server error.
+    Network(String),
+    /// The provider does not know the symbol.
+    NotFound,
+    /// The provider refuses requests until later.
+    RateLimited,
+    /// The answer or a local file could not be read.
+    Parse(String),
+    /// The symbol is known but has no bars in the requested range.
+    NoDataInRange,
+    Other(String),
+}
+
+impl DataError {
+    /// Whether retrying later may help.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, DataError::Network(_) | DataError::RateLimited)
+    }
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Network(message) => write!(f, "Network error: {}", message),
+            DataError::NotFound => write!(f, "Symbol not found"),
+            DataError::RateLimited => write!(f, "Too many requests, try again later"),
+            DataError::Parse(message) => write!(f, "Unreadable data: {}", message),
+            DataError::NoDataInRange => write!(f, "No data in the requested range"),
+            DataError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl From<&YahooError> for DataError {
+    fn from(error: &YahooError) -> Self {
+        match error {
+            YahooError::FetchFailed(status) if status.starts_with("404") => DataError::NotFound,
+            YahooError::FetchFailed(status) if status.starts_with("429") => DataError::RateLimited,
+            YahooError::FetchFailed(status) if status.starts_with('5') => {
+                DataError::Network(status.clone())
+            }
+            YahooError::FetchFailed(status) => DataError::Other(status.clone()),
+            YahooError::ConnectionFailed(e) => DataError::Network(e.to_string()),
+            YahooError::DeserializeFailed(e) => DataError::Parse(e.to_string()),
+            YahooError::InvalidJson | YahooError::DataInconsistency => {
+                DataError::Parse(error.to_string())
+            }
+            YahooError::EmptyDataSet => DataError::NoDataInRange,
+            YahooError::BuilderFailed => DataError::Other(error.to_string()),
+        }
+    }
+}
+
+/// Classifies the errors providers return, unknown ones keep their message.
+impl From<&anyhow::Error> for DataError {
+    fn from(error: &anyhow::Error) -> Self {
+        if let Some(error) = error.downcast_ref::<DataError>() {
+            return error.clone();
+        }
+
+        if let Some(error) = error.downcast_ref::<YahooError>() {
+            return error.into();
+        }
+
+        DataError::Other(format!("{:#}", error))
+    }
+}
+
+impl From<anyhow::Error> for DataError {
+    fn from(error: anyhow::Error) -> Self {
+        (&error).into()
+    }
+}
+
+/// A failed load as shown in the notifications panel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorRecord {
+    pub time: SystemTime,
+    /// What was being loaded, e.g. `AAPL 1d`.
+    pub context: String,
+    pub error: DataError,
+}
+
+#[derive(Default)]
+struct ErrorLogInner {
+    records: VecDeque<ErrorRecord>,
+    /// Errors pushed since the start, including dropped and cleared ones.
+    total: usize,
+}
+
+/// The latest errors, shared between the storage and the UI.
+#[derive(Clone, Default)]
+pub struct ErrorLog {
+    inner: Arc<Mutex<ErrorLogInner>>,
+}
+
+impl ErrorLog {
+    pub fn push(&self, context: String, error: DataError) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.records.len() == LOG_LENGTH {
+            inner.records.pop_front();
+        }
+
+        inner.records.push_back(ErrorRecord {
+            time: SystemTime::now(),
+            context,
+            error,
+        });
+        inner.total += 1;
+    }
+
+    /// Oldest first.
+    pub fn records(&self) -> Vec<ErrorRecord> {
+        self.inner.lock().unwrap().records.iter().cloned().collect()
+    }
+
+    /// Number of errors ever pushed, to tell which ones are new.
+    pub fn total(&self) -> usize {
+        self.inner.lock().unwrap().total
+    }
+
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().records.clear();
+    }
+}
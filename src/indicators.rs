@@ -0,0 +1,566 @@
+use serde::{Deserialize, Serialize};
+use yahoo_finance_api::Quote;
+
+/// A technical indicator together with its parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Indicator {
+    Sma {
+        period: usize,
+    },
+    Ema {
+        period: usize,
+    },
+    Wma {
+        period: usize,
+    },
+    Bollinger {
+        period: usize,
+        deviations: f64,
+    },
+    Vwap,
+    Rsi {
+        period: usize,
+    },
+    Macd {
+        fast: usize,
+        slow: usize,
+        signal: usize,
+    },
+    Stochastic {
+        k: usize,
+        d: usize,
+    },
+    Atr {
+        period: usize,
+    },
+    Obv,
+    Adx {
+        period: usize,
+    },
+}
+
+/// One output line of an indicator, aligned with the quotes it was computed
+/// from. Values are `None` until the indicator has enough bars.
+#[derive(Clone, Debug)]
+pub struct Series {
+    pub name: &'static str,
+    pub values: Vec<Option<f64>>,
+}
+
+impl Indicator {
+    /// Every indicator with its usual default parameters.
+    pub fn defaults() -> [Indicator; 11] {
+        [
+            Indicator::Sma { period: 20 },
+            Indicator::Ema { period: 20 },
+            Indicator::Wma { period: 20 },
+            Indicator::Bollinger {
+                period: 20,
+                deviations: 2.0,
+            },
+            Indicator::Vwap,
+            Indicator::Rsi { period: 14 },
+            Indicator::Macd {
+                fast: 12,
+                slow: 26,
+                signal: 9,
+            },
+            Indicator::Stochastic { k: 14, d: 3 },
+            Indicator::Atr { period: 14 },
+            Indicator::Obv,
+            Indicator::Adx { period: 14 },
+        ]
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Indicator::Sma { .. } => "SMA",
+            Indicator::Ema { .. } => "EMA",
+            Indicator::Wma { .. } => "WMA",
+            Indicator::Bollinger { .. } => "BB",
+            Indicator::Vwap => "VWAP",
+            Indicator::Rsi { .. } => "RSI",
+            Indicator::Macd { .. } => "MACD",
+            Indicator::Stochastic { .. } => "Stoch",
+            Indicator::Atr { .. } => "ATR",
+            Indicator::Obv => "OBV",
+            Indicator::Adx { .. } => "ADX",
+        }
+    }
+
+    /// Short label including the parameters, e.g. `MACD(12, 26, 9)`.
+    pub fn label(&self) -> String {
+        match self {
+            Indicator::Sma { period }
+            | Indicator::Ema { period }
+            | Indicator::Wma { period }
+            | Indicator::Rsi { period }
+            | Indicator::Atr { period }
+            | Indicator::Adx { period } => format!("{}({})", self.kind(), period),
+            Indicator::Bollinger { period, deviations } => {
+                format!("{}({}, {})", self.kind(), period, deviations)
+            }
+            Indicator::Macd { fast, slow, signal } => {
+                format!("{}({}, {}, {})", self.kind(), fast, slow, signal)
+            }
+            Indicator::Stochastic { k, d } => format!("{}({}, {})", self.kind(), k, d),
+            Indicator::Vwap | Indicator::Obv => self.kind().to_string(),
+        }
+    }
+
+    /// Whether the indicator is drawn on top of the prices or in its own pane.
+    pub fn is_overlay(&self) -> bool {
+        matches!(
+            self,
+            Indicator::Sma { .. }
+                | Indicator::Ema { .. }
+                | Indicator::Wma { .. }
+                | Indicator::Bollinger { .. }
+                | Indicator::Vwap
+        )
+    }
+
+    pub fn compute(&self, quotes: &[Quote]) -> Vec<Series> {
+        let closes = quotes
+            .iter()
+            .map(|quote| Some(quote.close))
+            .collect::<Vec<_>>();
+
+        let series = |name, values| Series { name, values };
+
+        match *self {
+            Indicator::Sma { period } => vec![series("SMA", sma(&closes, period))],
+            Indicator::Ema { period } => vec![series("EMA", ema(&closes, period))],
+            Indicator::Wma { period } => vec![series("WMA", wma(&closes, period))],
+            Indicator::Bollinger { period, deviations } => {
+                let (middle, upper, lower) = bollinger(&closes, period, deviations);
+
+                vec![
+                    series("Middle", middle),
+                    series("Upper", upper),
+                    series("Lower", lower),
+                ]
+            }
+            Indicator::Vwap => vec![series("VWAP", vwap(quotes))],
+            Indicator::Rsi { period } => vec![series("RSI", rsi(&closes, period))],
+            Indicator::Macd { fast, slow, signal } => {
+                let (macd, signal, histogram) = macd(&closes, fast, slow, signal);
+
+                vec![
+                    series("MACD", macd),
+                    series("Signal", signal),
+                    series("Histogram", histogram),
+                ]
+            }
+            Indicator::Stochastic { k, d } => {
+                let (k, d) = stochastic(quotes, k, d);
+
+                vec![series("%K", k), series("%D", d)]
+            }
+            Indicator::Atr { period } => vec![series("ATR", atr(quotes, period))],
+            Indicator::Obv => vec![series("OBV", obv(quotes))],
+            Indicator::Adx { period } => {
+                let (adx, plus, minus) = adx(quotes, period);
+
+                vec![
+                    series("ADX", adx),
+                    series("+DI", plus),
+                    series("-DI", minus),
+                ]
+            }
+        }
+    }
+}
+
+/// Parses `kind[:parameter...]`, e.g. `sma:50` or `macd:12:26:9`. Omitted
+/// parameters keep their defaults.
+impl std::str::FromStr for Indicator {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.split(':');
+        let kind = parts.next().unwrap_or_default();
+
+        let mut indicator = Indicator::defaults()
+            .into_iter()
+            .find(|indicator| indicator.kind().eq_ignore_ascii_case(kind))
+            .ok_or_else(|| anyhow::anyhow!("Unknown indicator: {}", kind))?;
+
+        let parameters = parts
+            .map(|part| {
+                part.parse::<f64>()
+                    .map_err(|_| anyhow::anyhow!("Invalid parameter {} in {}", part, value))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut fields: Vec<&mut dyn SetParameter> = match &mut indicator {
+            Indicator::Sma { period }
+            | Indicator::Ema { period }
+            | Indicator::Wma { period }
+            | Indicator::Rsi { period }
+            | Indicator::Atr { period }
+            | Indicator::Adx { period } => vec![period],
+            Indicator::Bollinger { period, deviations } => vec![period, deviations],
+            Indicator::Macd { fast, slow, signal } => vec![fast, slow, signal],
+            Indicator::Stochastic { k, d } => vec![k, d],
+            Indicator::Vwap | Indicator::Obv => Vec::new(),
+        };
+
+        anyhow::ensure!(
+            parameters.len() <= fields.len(),
+            "{} takes at most {} parameters",
+            kind,
+            fields.len()
+        );
+
+        for (field, parameter) in fields.iter_mut().zip(parameters) {
+            field.set(parameter);
+        }
+
+        Ok(indicator)
+    }
+}
+
+trait SetParameter {
+    fn set(&mut self, value: f64);
+}
+
+impl SetParameter for usize {
+    fn set(&mut self, value: f64) {
+        *self = value.max(1.0) as usize;
+    }
+}
+
+impl SetParameter for f64 {
+    fn set(&mut self, value: f64) {
+        *self = value;
+    }
+}
+
+pub fn sma(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    rolling(values, period, |window| {
+        window.iter().sum::<f64>() / window.len() as f64
+    })
+}
+
+pub fn wma(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    rolling(values, period, |window| {
+        let weights = (1..=window.len()).map(|weight| weight as f64);
+
+        let weighted = window.iter().zip(weights.clone()).map(|(v, w)| v * w);
+
+        weighted.sum::<f64>() / weights.sum::<f64>()
+    })
+}
+
+pub fn ema(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    smooth(values, period, 2.0 / (period.max(1) as f64 + 1.0))
+}
+
+/// Wilder's smoothing as used by RSI, ATR and ADX.
+pub fn wilder(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    smooth(values, period, 1.0 / period.max(1) as f64)
+}
+
+/// Returns the middle, upper and lower band.
+#[allow(clippy::type_complexity)]
+pub fn bollinger(
+    values: &[Option<f64>],
+    period: usize,
+    deviations: f64,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>, Vec<Option<f64>>) {
+    let middle = sma(values, period);
+
+    let deviation = rolling(values, period, |window| {
+        let mean = window.iter().sum::<f64>() / window.len() as f64;
+
+        let variance = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / window.len() as f64;
+
+        variance.sqrt()
+    });
+
+    let band = |sign: f64| {
+        middle
+            .iter()
+            .zip(&deviation)
+            .map(|(middle, deviation)| Some((*middle)? + sign * deviations * (*deviation)?))
+            .collect()
+    };
+
+    let upper = band(1.0);
+    let lower = band(-1.0);
+
+    (middle, upper, lower)
+}
+
+/// Volume weighted average of the typical price, anchored at the first quote.
+pub fn vwap(quotes: &[Quote]) -> Vec<Option<f64>> {
+    let mut volume = 0.0;
+    let mut weighted = 0.0;
+
+    quotes
+        .iter()
+        .map(|quote| {
+            volume += quote.volume as f64;
+            weighted += typical_price(quote) * quote.volume as f64;
+
+            (volume > 0.0).then(|| weighted / volume)
+        })
+        .collect()
+}
+
+pub fn rsi(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    let changes = differences(values);
+
+    let gains = wilder(
+        &changes
+            .iter()
+            .map(|c| c.map(|c| c.max(0.0)))
+            .collect::<Vec<_>>(),
+        period,
+    );
+    let losses = wilder(
+        &changes
+            .iter()
+            .map(|c| c.map(|c| (-c).max(0.0)))
+            .collect::<Vec<_>>(),
+        period,
+    );
+
+    gains
+        .iter()
+        .zip(&losses)
+        .map(|(gain, loss)| {
+            let (gain, loss) = ((*gain)?, (*loss)?);
+
+            if loss == 0.0 {
+                Some(100.0)
+            } else {
+                Some(100.0 - 100.0 / (1.0 + gain / loss))
+            }
+        })
+        .collect()
+}
+
+/// Returns the MACD line, the signal line and the histogram.
+#[allow(clippy::type_complexity)]
+pub fn macd(
+    values: &[Option<f64>],
+    fast: usize,
+    slow: usize,
+    signal: usize,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>, Vec<Option<f64>>) {
+    let fast = ema(values, fast);
+    let slow = ema(values, slow);
+
+    let line = fast
+        .iter()
+        .zip(&slow)
+        .map(|(fast, slow)| Some((*fast)? - (*slow)?))
+        .collect::<Vec<_>>();
+
+    let signal = ema(&line, signal);
+
+    let histogram = line
+        .iter()
+        .zip(&signal)
+        .map(|(line, signal)| Some((*line)? - (*signal)?))
+        .collect();
+
+    (line, signal, histogram)
+}
+
+/// Returns %K and %D.
+pub fn stochastic(
+    quotes: &[Quote],
+    k_period: usize,
+    d_period: usize,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>) {
+    let k_period = k_period.max(1);
+
+    let k = (0..quotes.len())
+        .map(|index| {
+            let window = &quotes[(index + 1).checked_sub(k_period)?..=index];
+
+            let highest = window.iter().map(|q| q.high).fold(f64::MIN, f64::max);
+            let lowest = window.iter().map(|q| q.low).fold(f64::MAX, f64::min);
+
+            if highest == lowest {
+                Some(50.0)
+            } else {
+                Some(100.0 * (quotes[index].close - lowest) / (highest - lowest))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let d = sma(&k, d_period);
+
+    (k, d)
+}
+
+pub fn atr(quotes: &[Quote], period: usize) -> Vec<Option<f64>> {
+    wilder(&true_range(quotes), period)
+}
+
+pub fn obv(quotes: &[Quote]) -> Vec<Option<f64>> {
+    let mut total = 0.0;
+
+    quotes
+        .iter()
+        .enumerate()
+        .map(|(index, quote)| {
+            if let Some(previous) = index.checked_sub(1).map(|index| &quotes[index]) {
+                if quote.close > previous.close {
+                    total += quote.volume as f64;
+                } else if quote.close < previous.close {
+                    total -= quote.volume as f64;
+                }
+            }
+
+            Some(total)
+        })
+        .collect()
+}
+
+/// Returns ADX, +DI and -DI.
+#[allow(clippy::type_complexity)]
+pub fn adx(
+    quotes: &[Quote],
+    period: usize,
+) -> (Vec<Option<f64>>, Vec<Option<f64>>, Vec<Option<f64>>) {
+    let mut plus_movement = vec![None; quotes.len()];
+    let mut minus_movement = vec![None; quotes.len()];
+    let mut ranges = true_range(quotes);
+
+    if let Some(first) = ranges.first_mut() {
+        *first = None;
+    }
+
+    for index in 1..quotes.len() {
+        let up = quotes[index].high - quotes[index - 1].high;
+        let down = quotes[index - 1].low - quotes[index].low;
+
+        plus_movement[index] = Some(if up > down && up > 0.0 { up } else { 0.0 });
+        minus_movement[index] = Some(if down > up && down > 0.0 { down } else { 0.0 });
+    }
+
+    let ranges = wilder(&ranges, period);
+
+    let indicator = |movement: &[Option<f64>]| {
+        wilder(movement, period)
+            .iter()
+            .zip(&ranges)
+            .map(|(movement, range)| {
+                let (movement, range) = ((*movement)?, (*range)?);
+
+                (range > 0.0).then(|| 100.0 * movement / range)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let plus = indicator(&plus_movement);
+    let minus = indicator(&minus_movement);
+
+    let dx = plus
+        .iter()
+        .zip(&minus)
+        .map(|(plus, minus)| {
+            let (plus, minus) = ((*plus)?, (*minus)?);
+
+            if plus + minus == 0.0 {
+                Some(0.0)
+            } else {
+                Some(100.0 * (plus - minus).abs() / (plus + minus))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    (wilder(&dx, period), plus, minus)
+}
+
+fn typical_price(quote: &Quote) -> f64 {
+    (quote.high + quote.low + quote.close) / 3.0
+}
+
+fn true_range(quotes: &[Quote]) -> Vec<Option<f64>> {
+    quotes
+        .iter()
+        .enumerate()
+        .map(|(index, quote)| {
+            let range = quote.high - quote.low;
+
+            Some(
+                match index.checked_sub(1).map(|index| quotes[index].close) {
+                    Some(previous_close) => range
+                        .max((quote.high - previous_close).abs())
+                        .max((quote.low - previous_close).abs()),
+                    None => range,
+                },
+            )
+        })
+        .collect()
+}
+
+fn differences(values: &[Option<f64>]) -> Vec<Option<f64>> {
+    std::iter::once(None)
+        .chain(values.windows(2).map(|pair| Some(pair[1]? - pair[0]?)))
+        .take(values.len())
+        .collect()
+}
+
+/// Applies `function` to every full window of `period` values.
+fn rolling(
+    values: &[Option<f64>],
+    period: usize,
+    function: impl Fn(&[f64]) -> f64,
+) -> Vec<Option<f64>> {
+    let period = period.max(1);
+    let mut window = Vec::with_capacity(period);
+
+    (0..values.len())
+        .map(|index| {
+            let start = (index + 1).checked_sub(period)?;
+
+            window.clear();
+
+            for value in &values[start..=index] {
+                window.push((*value)?);
+            }
+
+            Some(function(&window))
+        })
+        .collect()
+}
+
+/// Exponential smoothing seeded with the mean of the first `period` values.
+/// Leading `None`s are skipped.
+fn smooth(values: &[Option<f64>], period: usize, alpha: f64) -> Vec<Option<f64>> {
+    let period = period.max(1);
+    let mut result = vec![None; values.len()];
+
+    let Some(first) = values.iter().position(Option::is_some) else {
+        return result;
+    };
+
+    let seed_end = first + period;
+
+    if seed_end > values.len() {
+        return result;
+    }
+
+    let Some(seed) = values[first..seed_end].iter().copied().sum::<Option<f64>>() else {
+        return result;
+    };
+
+    let mut previous = seed / period as f64;
+    result[seed_end - 1] = Some(previous);
+
+    for index in seed_end..values.len() {
+        if let Some(value) = values[index] {
+            previous = alpha * value + (1.0 - alpha) * previous;
+            result[index] = Some(previous);
+        }
+    }
+
+    result
+}
@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::resample::Bucket;
+
+/// Bar length of a quote series.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum Interval {
+    OneMinute,
+    FiveMinutes,
+    FifteenMinutes,
+    OneHour,
+    #[default]
+    OneDay,
+    OneWeek,
+    OneMonth,
+    OneQuarter,
+}
+
+impl Interval {
+    pub const ALL: [Interval; 8] = [
+        Interval::OneMinute,
+        Interval::FiveMinutes,
+        Interval::FifteenMinutes,
+        Interval::OneHour,
+        Interval::OneDay,
+        Interval::OneWeek,
+        Interval::OneMonth,
+        Interval::OneQuarter,
+    ];
+
+    /// The code Yahoo uses for this interval, also used for display.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Interval::OneMinute => "1m",
+            Interval::FiveMinutes => "5m",
+            Interval::FifteenMinutes => "15m",
+            Interval::OneHour => "1h",
+            Interval::OneDay => "1d",
+            Interval::OneWeek => "1wk",
+            Interval::OneMonth => "1mo",
+            Interval::OneQuarter => "3mo",
+        }
+    }
+
+    /// Nominal bar length in seconds, months count as 30 days.
+    pub fn seconds(&self) -> u64 {
+        match self {
+            Interval::OneMinute => 60,
+            Interval::FiveMinutes => 5 * 60,
+            Interval::FifteenMinutes => 15 * 60,
+            Interval::OneHour => 60 * 60,
+            Interval::OneDay => 24 * 60 * 60,
+            Interval::OneWeek => 7 * 24 * 60 * 60,
+            Interval::OneMonth => 30 * 24 * 60 * 60,
+            Interval::OneQuarter => 90 * 24 * 60 * 60,
+        }
+    }
+
+    pub fn is_intraday(&self) -> bool {
+        *self < Interval::OneDay
+    }
+
+    /// How far back Yahoo serves bars of this interval.
+    ///
+    /// 1m bars are limited to 7 days per request, 5m and 15m to the last 60
+    /// days and hourly bars to the last 730 days.
+    pub fn max_lookback(&self) -> Option<Duration> {
+        const DAY: u64 = 24 * 60 * 60;
+
+        match self {
+            Interval::OneMinute => Some(Duration::from_secs(7 * DAY)),
+            Interval::FiveMinutes | Interval::FifteenMinutes => Some(Duration::from_secs(59 * DAY)),
+            Interval::OneHour => Some(Duration::from_secs(729 * DAY)),
+            Interval::OneDay | Interval::OneWeek | Interval::OneMonth | Interval::OneQuarter => {
+                None
+            }
+        }
+    }
+
+    /// The interval that is actually fetched and stored for this one, and the
+    /// bucket to resample it with if they differ.
+    ///
+    /// Weekly and longer bars are built from the cached daily bars, 15m bars
+    /// from the 5m ones, so switching between them needs no download.
+    pub fn source(&self) -> (Interval, Option<Bucket>) {
+        match self {
+            Interval::FifteenMinutes => (Interval::FiveMinutes, Some(Bucket::Minutes(15))),
+            Interval::OneWeek => (Interval::OneDay, Some(Bucket::Weeks(1))),
+            Interval::OneMonth => (Interval::OneDay, Some(Bucket::Months(1))),
+            Interval::OneQuarter => (Interval::OneDay, Some(Bucket::Quarters(1))),
+            _ => (*self, None),
+        }
+    }
+
+    /// Minimum time between two refreshes of a series.
+    pub fn update_interval(&self) -> Duration {
+        Duration::from_secs(self.seconds().min(24 * 60 * 60))
+    }
+
+    /// `chrono` format string for timestamps of bars with this interval.
+    pub fn date_format(&self) -> &'static str {
+        if self.is_intraday() {
+            "%Y-%m-%d %H:%M"
+        } else {
+            "%Y-%m-%d"
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Interval {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Interval::ALL
+            .into_iter()
+            .find(|interval| interval.as_str() == value)
+            .ok_or_else(|| anyhow::anyhow!("Unknown interval: {}", value))
+    }
+}
@@ -0,0 +1,48 @@
+//! JSON files in the data directory, shared by the ledger and the alerts.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// `<data dir>/market-view/<file_name>`, none if there is no data directory.
+pub(crate) fn data_file(file_name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("market-view").join(file_name))
+}
+
+/// Reads `path`, none if it is missing. An unreadable file is logged with
+/// `what` as its description.
+pub(crate) fn load_json<T: DeserializeOwned>(path: &Path, what: &str) -> Option<T> {
+    let result = (|| -> anyhow::Result<T> {
+        let file = std::fs::File::open(path)?;
+
+        serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Invalid {}", what))
+    })();
+
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            if path.exists() {
+                log::error!("Error reading {} {}: {:#}", what, path.display(), e);
+            }
+
+            None
+        }
+    }
+}
+
+/// Writes `value` to a temporary file renamed over `path`, so a crash never
+/// leaves a truncated file behind.
+pub(crate) fn save_json_atomic<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+
+    let temporary = path.with_extension("json.tmp");
+
+    std::fs::write(&temporary, serde_json::to_vec_pretty(value)?)?;
+    std::fs::rename(&temporary, path)?;
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! Market data core of Market View: providers, the cached quote storage,
+//! resampling and the analytics built on top.
+//!
+//! Nothing in here is global. A [`Storage`] is created from a provider and a
+//! tokio runtime handle and passed to whatever needs quotes.
+
+pub mod alerts;
+pub mod backtest;
+pub mod cache;
+pub mod error;
+pub mod indicators;
+pub mod interval;
+mod json;
+pub mod portfolio;
+pub mod providers;
+pub mod requests;
+pub mod resample;
+pub mod scheduler;
+pub mod storage;
+
+pub use error::DataError;
+pub use interval::Interval;
+pub use providers::{History, MarketDataProvider};
+pub use storage::Storage;
@@ -1,19 +1,50 @@
-#![feature(lazy_cell)]
+use std::sync::Arc;
 
+use clap::Parser;
 use eframe::NativeOptions;
 use egui::ViewportBuilder;
+use market_view::{requests::RequestLog, MarketDataProvider, Storage};
 
+mod annotations;
 mod app;
-mod requests;
-mod storage;
+mod cli;
 mod windows;
+mod workspace;
 
 fn main() {
+    let cli = cli::Cli::parse();
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .expect("Failed to build the tokio runtime");
+
+    let requests = RequestLog::default();
+
+    if let Some(command) = cli.command {
+        let _ = simple_logger::SimpleLogger::new()
+            .with_level(log::LevelFilter::Warn)
+            .with_module_level("market_view", log::LevelFilter::Info)
+            .init();
+
+        let storage = storage(&runtime, &requests);
+
+        if let Err(e) = cli::run(&storage, command) {
+            log::error!("{:#}", e);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
     let _ = simple_logger::SimpleLogger::new()
         .with_level(log::LevelFilter::Info)
         .with_module_level("market_view", log::LevelFilter::Debug)
         .init();
 
+    let storage = storage(&runtime, &requests);
+
     let native_options = NativeOptions {
         viewport: ViewportBuilder::default()
             .with_maximized(true)
@@ -24,7 +55,14 @@ fn main() {
     eframe::run_native(
         "Market View",
         native_options,
-        Box::new(|cc| Box::new(app::App::new(cc))),
+        Box::new(move |cc| Box::new(app::App::new(cc, storage, requests))),
     )
     .expect("Failed to run native");
 }
+
+/// Storage backed by the provider picked from the environment.
+fn storage(runtime: &tokio::runtime::Runtime, requests: &RequestLog) -> Storage {
+    let provider: Arc<dyn MarketDataProvider> = market_view::providers::from_env(requests).into();
+
+    Storage::load(provider, runtime.handle().clone())
+}
@@ -0,0 +1,321 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use yahoo_finance_api::Quote;
+
+use crate::json;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TransactionKind {
+    Buy { quantity: f64, price: f64 },
+    Sell { quantity: f64, price: f64 },
+    Dividend { amount: f64 },
+    Fee { amount: f64 },
+}
+
+impl TransactionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionKind::Buy { .. } => "Buy",
+            TransactionKind::Sell { .. } => "Sell",
+            TransactionKind::Dividend { .. } => "Dividend",
+            TransactionKind::Fee { .. } => "Fee",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    /// Unix seconds.
+    pub timestamp: u64,
+    pub symbol: String,
+    pub kind: TransactionKind,
+    /// Commission paid on top of the transaction.
+    pub commission: f64,
+}
+
+/// How the cost of sold shares is determined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostBasis {
+    /// Sells consume the oldest lots first.
+    #[default]
+    Fifo,
+    /// Every share costs the average price paid.
+    Average,
+}
+
+impl CostBasis {
+    pub const ALL: [CostBasis; 2] = [CostBasis::Fifo, CostBasis::Average];
+}
+
+impl std::fmt::Display for CostBasis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CostBasis::Fifo => f.write_str("FIFO"),
+            CostBasis::Average => f.write_str("Average"),
+        }
+    }
+}
+
+/// Holdings of one symbol after applying the ledger.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Position {
+    pub symbol: String,
+    pub quantity: f64,
+    /// Total cost of the shares still held.
+    pub cost: f64,
+    pub realized: f64,
+    pub dividends: f64,
+    /// Commissions plus standalone fees.
+    pub fees: f64,
+    /// Open lots as quantity and price, oldest first.
+    lots: VecDeque<(f64, f64)>,
+}
+
+impl Position {
+    pub fn average_cost(&self) -> Option<f64> {
+        (self.quantity > 0.0).then(|| self.cost / self.quantity)
+    }
+
+    pub fn market_value(&self, price: f64) -> f64 {
+        self.quantity * price
+    }
+
+    pub fn unrealized(&self, price: f64) -> f64 {
+        self.market_value(price) - self.cost
+    }
+
+    /// Realized gains plus dividends minus fees, excluding unrealized gains.
+    pub fn income(&self) -> f64 {
+        self.realized + self.dividends - self.fees
+    }
+
+    fn apply(&mut self, transaction: &Transaction, cost_basis: CostBasis) {
+        self.fees += transaction.commission;
+
+        match transaction.kind {
+            TransactionKind::Buy { quantity, price } => {
+                self.quantity += quantity;
+                self.cost += quantity * price;
+                self.lots.push_back((quantity, price));
+            }
+            TransactionKind::Sell { quantity, price } => {
+                let sold = quantity.min(self.quantity);
+
+                if sold < quantity {
+                    log::warn!(
+                        "Selling {} {} but only {} are held",
+                        quantity,
+                        transaction.symbol,
+                        self.quantity
+                    );
+                }
+
+                let cost = match cost_basis {
+                    CostBasis::Fifo => self.consume_lots(sold),
+                    CostBasis::Average => {
+                        let cost = self.average_cost().unwrap_or_default() * sold;
+
+                        self.consume_lots(sold);
+
+                        cost
+                    }
+                };
+
+                self.quantity -= sold;
+                self.cost = if self.quantity > 0.0 {
+                    self.cost - cost
+                } else {
+                    0.0
+                };
+                self.realized += sold * price - cost;
+            }
+            TransactionKind::Dividend { amount } => self.dividends += amount,
+            TransactionKind::Fee { amount } => self.fees += amount,
+        }
+    }
+
+    /// Removes `quantity` shares from the oldest lots and returns their cost.
+    fn consume_lots(&mut self, mut quantity: f64) -> f64 {
+        let mut cost = 0.0;
+
+        while quantity > 0.0 {
+            let Some((lot_quantity, price)) = self.lots.front_mut() else {
+                break;
+            };
+
+            let taken = quantity.min(*lot_quantity);
+
+            cost += taken * *price;
+            quantity -= taken;
+            *lot_quantity -= taken;
+
+            if *lot_quantity <= f64::EPSILON {
+                self.lots.pop_front();
+            }
+        }
+
+        cost
+    }
+}
+
+/// All recorded transactions, stored as JSON next to the quote cache.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Ledger {
+    pub cost_basis: CostBasis,
+    pub transactions: Vec<Transaction>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Ledger {
+    /// `<data dir>/market-view/ledger.json`, empty if it does not exist yet.
+    pub fn load() -> Self {
+        let Some(path) = json::data_file("ledger.json") else {
+            log::warn!("No data directory found, the ledger will not be saved");
+            return Ledger::default();
+        };
+
+        let mut ledger: Ledger = json::load_json(&path, "ledger").unwrap_or_default();
+
+        ledger.path = Some(path);
+
+        ledger
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        if let Err(e) = json::save_json_atomic(path, self) {
+            log::error!("Error saving ledger {}: {:#}", path.display(), e);
+        }
+    }
+
+    pub fn add(&mut self, transaction: Transaction) {
+        let index = self
+            .transactions
+            .partition_point(|existing| existing.timestamp <= transaction.timestamp);
+
+        self.transactions.insert(index, transaction);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.transactions.len() {
+            self.transactions.remove(index);
+        }
+    }
+
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols = self
+            .transactions
+            .iter()
+            .map(|transaction| transaction.symbol.clone())
+            .collect::<Vec<_>>();
+
+        symbols.sort();
+        symbols.dedup();
+
+        symbols
+    }
+
+    /// Positions after all transactions, sorted by symbol.
+    pub fn positions(&self) -> Vec<Position> {
+        let mut positions = BTreeMap::new();
+
+        for transaction in &self.transactions {
+            positions
+                .entry(transaction.symbol.clone())
+                .or_insert_with(|| Position {
+                    symbol: transaction.symbol.clone(),
+                    ..Position::default()
+                })
+                .apply(transaction, self.cost_basis);
+        }
+
+        positions.into_values().collect()
+    }
+
+    /// Portfolio value for every bar from the first transaction on.
+    ///
+    /// The value is the market value of the open positions plus a cash
+    /// balance: sales and dividends add to it, buys, commissions and fees are
+    /// paid from it, and whatever it cannot cover counts as deposited. So the
+    /// curve starts at the invested amount and a sale at the bar's close
+    /// leaves it unchanged. Each symbol is valued at its latest close not
+    /// after the bar.
+    pub fn equity_curve(&self, quotes: &HashMap<String, Vec<Quote>>) -> Vec<[f64; 2]> {
+        let Some(first) = self.transactions.first() else {
+            return Vec::new();
+        };
+
+        let mut timestamps = quotes
+            .values()
+            .flatten()
+            .map(|quote| quote.timestamp)
+            .filter(|timestamp| *timestamp >= first.timestamp)
+            .collect::<Vec<_>>();
+
+        timestamps.sort_unstable();
+        timestamps.dedup();
+
+        let mut positions: HashMap<&str, Position> = HashMap::new();
+        let mut cash = 0.0;
+        let mut closes: HashMap<&str, f64> = HashMap::new();
+        let mut next_quote: HashMap<&str, usize> = HashMap::new();
+        let mut transactions = self.transactions.iter().peekable();
+
+        timestamps
+            .into_iter()
+            .map(|timestamp| {
+                while let Some(transaction) =
+                    transactions.next_if(|transaction| transaction.timestamp <= timestamp)
+                {
+                    let position = positions.entry(&transaction.symbol).or_default();
+                    let held = position.quantity;
+
+                    position.apply(transaction, self.cost_basis);
+
+                    cash += match transaction.kind {
+                        TransactionKind::Buy { quantity, price } => -quantity * price,
+                        TransactionKind::Sell { price, .. } => (held - position.quantity) * price,
+                        TransactionKind::Dividend { amount } => amount,
+                        TransactionKind::Fee { amount } => -amount,
+                    } - transaction.commission;
+
+                    // Money not in the account is deposited.
+                    cash = f64::max(cash, 0.0);
+                }
+
+                for (symbol, series) in quotes {
+                    let next = next_quote.entry(symbol).or_default();
+
+                    while let Some(quote) = series.get(*next).filter(|q| q.timestamp <= timestamp) {
+                        closes.insert(symbol, quote.close);
+                        *next += 1;
+                    }
+                }
+
+                let value = cash
+                    + positions
+                        .iter()
+                        .map(|(symbol, position)| {
+                            let price = closes
+                                .get(symbol)
+                                .copied()
+                                .or_else(|| position.average_cost())
+                                .unwrap_or_default();
+
+                            position.market_value(price)
+                        })
+                        .sum::<f64>();
+
+                [timestamp as f64, value]
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,99 @@
+mod csv;
+mod fake;
+mod yahoo;
+
+use futures::future::BoxFuture;
+use yahoo_finance_api::{time::OffsetDateTime, Quote, YQuoteItem};
+
+use crate::{
+    interval::Interval,
+    requests::{RequestLayer, RequestLog, RequestPolicy},
+};
+
+pub use self::csv::{CsvColumns, CsvProvider};
+pub use fake::FakeProvider;
+pub use yahoo::YahooProvider;
+
+/// Bars returned by a provider.
+#[derive(Clone, Debug)]
+pub struct History {
+    pub quotes: Vec<Quote>,
+    /// Offset of the exchange's time zone from UTC in seconds.
+    pub utc_offset: i32,
+}
+
+/// A source of market data.
+///
+/// Everything in the app that needs quotes goes through this trait, so a
+/// backend can be swapped at startup without touching storage or windows.
+pub trait MarketDataProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<YQuoteItem>>>;
+
+    /// Bars for `symbol` between `start` and `end` (inclusive).
+    fn history<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> BoxFuture<'a, anyhow::Result<History>>;
+
+    /// The most recent quote of `symbol`, the forming bar while the market
+    /// is open.
+    fn latest_quote<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, anyhow::Result<Quote>>;
+}
+
+/// Picks the provider from the `MARKET_VIEW_PROVIDER` environment variable,
+/// falling back to Yahoo.
+///
+/// If `MARKET_VIEW_DATA_DIR` is set, the CSV files in it are served in front
+/// of the selected provider. `MARKET_VIEW_PROVIDER=csv` uses them alone.
+///
+/// Requests to the remote provider are rate limited and retried, their
+/// outcomes go to `requests`.
+pub fn from_env(requests: &RequestLog) -> Box<dyn MarketDataProvider> {
+    let name = std::env::var("MARKET_VIEW_PROVIDER").unwrap_or_default();
+
+    let remote: Option<Box<dyn MarketDataProvider>> = match name.as_str() {
+        "csv" => None,
+        "fake" => Some(Box::new(FakeProvider::default())),
+        "" | "yahoo" => Some(Box::new(YahooProvider::default())),
+        other => {
+            log::warn!("Unknown provider {}, using yahoo", other);
+            Some(Box::new(YahooProvider::default()))
+        }
+    };
+
+    let limited = |remote: Box<dyn MarketDataProvider>| -> Box<dyn MarketDataProvider> {
+        Box::new(RequestLayer::new(
+            remote,
+            RequestPolicy::default(),
+            requests.clone(),
+        ))
+    };
+
+    let remote = remote.map(limited);
+
+    let provider = match std::env::var_os("MARKET_VIEW_DATA_DIR") {
+        Some(directory) => {
+            let mapping = std::env::var("MARKET_VIEW_CSV_COLUMNS").unwrap_or_default();
+
+            let columns = CsvColumns::parse(&mapping).unwrap_or_else(|e| {
+                log::error!("Error parsing MARKET_VIEW_CSV_COLUMNS: {}", e);
+                CsvColumns::default()
+            });
+
+            Box::new(CsvProvider::new(directory.into(), columns, remote))
+        }
+        None => remote.unwrap_or_else(|| {
+            log::warn!("MARKET_VIEW_DATA_DIR is not set, using yahoo");
+            limited(Box::<YahooProvider>::default())
+        }),
+    };
+
+    log::info!("Using {} market data provider", provider.name());
+
+    provider
+}
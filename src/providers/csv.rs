@@ -0,0 +1,316 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use futures::future::BoxFuture;
+use yahoo_finance_api::{time::OffsetDateTime, Quote, YQuoteItem};
+
+use super::{History, MarketDataProvider};
+use crate::{error::DataError, interval::Interval};
+
+/// Header names of the columns that are mapped into a [`Quote`].
+///
+/// Headers are matched case-insensitively. `volume` and `adjclose` are
+/// optional in the files; a missing `adjclose` falls back to `close`.
+#[derive(Clone, Debug)]
+pub struct CsvColumns {
+    pub timestamp: String,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume: String,
+    pub adjclose: String,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        CsvColumns {
+            timestamp: "timestamp".to_string(),
+            open: "open".to_string(),
+            high: "high".to_string(),
+            low: "low".to_string(),
+            close: "close".to_string(),
+            volume: "volume".to_string(),
+            adjclose: "adjclose".to_string(),
+        }
+    }
+}
+
+impl CsvColumns {
+    /// Parses a mapping like `timestamp=Date,adjclose=Adj Close`, keeping the
+    /// defaults for every column that is not mentioned.
+    pub fn parse(mapping: &str) -> anyhow::Result<Self> {
+        let mut columns = CsvColumns::default();
+
+        for entry in mapping.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let (field, header) = entry
+                .split_once('=')
+                .with_context(|| format!("Invalid column mapping: {}", entry))?;
+
+            let header = header.trim().to_string();
+
+            match field.trim() {
+                "timestamp" => columns.timestamp = header,
+                "open" => columns.open = header,
+                "high" => columns.high = header,
+                "low" => columns.low = header,
+                "close" => columns.close = header,
+                "volume" => columns.volume = header,
+                "adjclose" => columns.adjclose = header,
+                other => anyhow::bail!("Unknown column: {}", other),
+            }
+        }
+
+        Ok(columns)
+    }
+}
+
+/// Reads daily bars from a directory holding one `<SYMBOL>.csv` per symbol.
+///
+/// Symbols that have no file, and intervals other than daily, are passed on
+/// to the optional fallback provider.
+pub struct CsvProvider {
+    directory: PathBuf,
+    columns: CsvColumns,
+    fallback: Option<Box<dyn MarketDataProvider>>,
+}
+
+impl CsvProvider {
+    pub fn new(
+        directory: PathBuf,
+        columns: CsvColumns,
+        fallback: Option<Box<dyn MarketDataProvider>>,
+    ) -> Self {
+        CsvProvider {
+            directory,
+            columns,
+            fallback,
+        }
+    }
+
+    fn symbols(&self) -> Vec<String> {
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::error!("Error reading {}: {}", self.directory.display(), e);
+                return Vec::new();
+            }
+        };
+
+        let mut symbols = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+            })
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect::<Vec<_>>();
+
+        symbols.sort();
+
+        symbols
+    }
+
+    /// The file of `symbol`, none if it does not exist or the symbol is not a
+    /// plain file name, so "../x" cannot read outside the directory.
+    fn path(&self, symbol: &str) -> Option<PathBuf> {
+        if symbol.is_empty()
+            || symbol.starts_with('.')
+            || symbol.contains(|c: char| c == '/' || c == '\\' || c == ':' || c.is_control())
+        {
+            log::debug!(
+                "Not looking for {:?} in {}",
+                symbol,
+                self.directory.display()
+            );
+            return None;
+        }
+
+        let path = self.directory.join(format!("{}.csv", symbol));
+
+        path.is_file().then_some(path)
+    }
+
+    fn fallback(&self, symbol: &str) -> anyhow::Result<&dyn MarketDataProvider> {
+        self.fallback.as_deref().ok_or_else(|| {
+            log::debug!("No local data for {}", symbol);
+            DataError::NotFound.into()
+        })
+    }
+
+    async fn read(&self, path: PathBuf) -> anyhow::Result<Vec<Quote>> {
+        let columns = self.columns.clone();
+
+        tokio::task::spawn_blocking(move || read_quotes(&path, &columns))
+            .await?
+            .map_err(|e| DataError::Parse(format!("{:#}", e)).into())
+    }
+}
+
+impl MarketDataProvider for CsvProvider {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<YQuoteItem>>> {
+        Box::pin(async move {
+            let needle = query.to_uppercase();
+
+            let mut results = self
+                .symbols()
+                .into_iter()
+                .filter(|symbol| symbol.to_uppercase().contains(&needle))
+                .map(|symbol| YQuoteItem {
+                    exchange: "LOCAL".to_string(),
+                    short_name: symbol.clone(),
+                    quote_type: "EQUITY".to_string(),
+                    index: "quotes".to_string(),
+                    score: 1.0,
+                    type_display: "Local".to_string(),
+                    long_name: format!("{} (local)", symbol),
+                    is_yahoo_finance: false,
+                    symbol,
+                })
+                .collect::<Vec<_>>();
+
+            if let Some(fallback) = &self.fallback {
+                match fallback.search(query).await {
+                    Ok(remote) => results.extend(remote),
+                    Err(e) => log::warn!("Fallback search for {} failed: {}", query, e),
+                }
+            }
+
+            Ok(results)
+        })
+    }
+
+    fn history<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> BoxFuture<'a, anyhow::Result<History>> {
+        Box::pin(async move {
+            let path = match self.path(symbol) {
+                Some(path) if interval == Interval::OneDay => path,
+                _ => {
+                    return self
+                        .fallback(symbol)?
+                        .history(symbol, start, end, interval)
+                        .await
+                }
+            };
+
+            let start = start.unix_timestamp();
+            let end = end.unix_timestamp();
+
+            // Timestamps without a zone are read as UTC, so no offset applies.
+            Ok(History {
+                quotes: self
+                    .read(path)
+                    .await?
+                    .into_iter()
+                    .filter(|quote| (start..=end).contains(&(quote.timestamp as i64)))
+                    .collect(),
+                utc_offset: 0,
+            })
+        })
+    }
+
+    fn latest_quote<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, anyhow::Result<Quote>> {
+        Box::pin(async move {
+            let Some(path) = self.path(symbol) else {
+                return self.fallback(symbol)?.latest_quote(symbol).await;
+            };
+
+            self.read(path)
+                .await?
+                .pop()
+                .with_context(|| format!("{} contains no quotes", symbol))
+        })
+    }
+}
+
+fn read_quotes(path: &Path, columns: &CsvColumns) -> anyhow::Result<Vec<Quote>> {
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("Error opening {}", path.display()))?;
+
+    let headers = reader.headers()?.clone();
+
+    let index = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.trim().eq_ignore_ascii_case(name))
+    };
+
+    let required = |name: &str| {
+        index(name).with_context(|| format!("{} has no column {}", path.display(), name))
+    };
+
+    let timestamp = required(&columns.timestamp)?;
+    let open = required(&columns.open)?;
+    let high = required(&columns.high)?;
+    let low = required(&columns.low)?;
+    let close = required(&columns.close)?;
+    let volume = index(&columns.volume);
+    let adjclose = index(&columns.adjclose);
+
+    let mut quotes = Vec::new();
+
+    for (line, record) in reader.records().enumerate() {
+        let record = record?;
+
+        let field = |column: usize| record.get(column).unwrap_or_default().trim();
+
+        let number = |column: usize| -> anyhow::Result<f64> {
+            field(column)
+                .parse()
+                .with_context(|| format!("{}:{}: invalid number", path.display(), line + 2))
+        };
+
+        let close_value = number(close)?;
+
+        quotes.push(Quote {
+            timestamp: parse_timestamp(field(timestamp))
+                .with_context(|| format!("{}:{}: invalid timestamp", path.display(), line + 2))?,
+            open: number(open)?,
+            high: number(high)?,
+            low: number(low)?,
+            volume: match volume {
+                Some(volume) => number(volume)? as u64,
+                None => 0,
+            },
+            close: close_value,
+            adjclose: match adjclose {
+                Some(adjclose) => number(adjclose)?,
+                None => close_value,
+            },
+        });
+    }
+
+    quotes.sort_by_key(|quote| quote.timestamp);
+
+    Ok(quotes)
+}
+
+/// Accepts unix seconds, `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS` (UTC) and RFC 3339.
+/// Dates before 1970 are rejected, quotes can't represent them.
+fn parse_timestamp(value: &str) -> anyhow::Result<u64> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(seconds);
+    }
+
+    let seconds = if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(value) {
+        datetime.timestamp()
+    } else if let Ok(datetime) = chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        datetime.and_utc().timestamp()
+    } else {
+        let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")?;
+
+        date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp()
+    };
+
+    u64::try_from(seconds).map_err(|_| anyhow::anyhow!("{} is before 1970", value))
+}
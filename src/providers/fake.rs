@@ -0,0 +1,129 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use futures::future::BoxFuture;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use yahoo_finance_api::{time::OffsetDateTime, Quote, YQuoteItem};
+
+use super::{History, MarketDataProvider};
+use crate::interval::Interval;
+
+/// Never returns more bars than this for a single request.
+const MAX_BARS: i64 = 5000;
+
+/// Offline provider producing deterministic synthetic bars.
+///
+/// The price at a given timestamp only depends on the symbol and the
+/// timestamp, so overlapping requests return matching bars.
+pub struct FakeProvider {
+    symbols: Vec<String>,
+}
+
+impl Default for FakeProvider {
+    fn default() -> Self {
+        FakeProvider {
+            symbols: ["FAKE", "TEST", "DEMO"]
+                .iter()
+                .map(|symbol| symbol.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl FakeProvider {
+    fn seed(symbol: &str, timestamp: i64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        symbol.hash(&mut hasher);
+        timestamp.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn price(symbol: &str, timestamp: i64) -> f64 {
+        let base = 20.0 + (Self::seed(symbol, 0) % 200) as f64;
+        let t = timestamp as f64;
+
+        let trend = 0.3 * (t / 31_536_000.0).sin() + 0.1 * (t / 2_592_000.0).sin();
+        let noise = StdRng::seed_from_u64(Self::seed(symbol, timestamp)).gen_range(-0.01..0.01);
+
+        base * (1.0 + trend + noise)
+    }
+
+    fn bar(symbol: &str, timestamp: i64, step: i64) -> Quote {
+        let open = Self::price(symbol, timestamp - step);
+        let close = Self::price(symbol, timestamp);
+
+        let mut rng = StdRng::seed_from_u64(Self::seed(symbol, -timestamp));
+
+        let high = open.max(close) * (1.0 + rng.gen_range(0.0..0.01));
+        let low = open.min(close) * (1.0 - rng.gen_range(0.0..0.01));
+
+        Quote {
+            timestamp: timestamp as u64,
+            open,
+            high,
+            low,
+            volume: rng.gen_range(100_000..10_000_000),
+            close,
+            adjclose: close,
+        }
+    }
+}
+
+impl MarketDataProvider for FakeProvider {
+    fn name(&self) -> &str {
+        "fake"
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<YQuoteItem>>> {
+        Box::pin(async move {
+            let query = query.to_uppercase();
+
+            Ok(self
+                .symbols
+                .iter()
+                .filter(|symbol| symbol.contains(&query))
+                .map(|symbol| YQuoteItem {
+                    exchange: "FAKE".to_string(),
+                    short_name: symbol.clone(),
+                    quote_type: "EQUITY".to_string(),
+                    symbol: symbol.clone(),
+                    index: "quotes".to_string(),
+                    score: 1.0,
+                    type_display: "Equity".to_string(),
+                    long_name: format!("{} (synthetic)", symbol),
+                    is_yahoo_finance: false,
+                })
+                .collect())
+        })
+    }
+
+    fn history<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> BoxFuture<'a, anyhow::Result<History>> {
+        Box::pin(async move {
+            let step = interval.seconds() as i64;
+
+            let end = end.unix_timestamp() / step * step;
+            let start = start.unix_timestamp().max(end - MAX_BARS * step);
+
+            Ok(History {
+                quotes: (start / step..=end / step)
+                    .map(|index| Self::bar(symbol, index * step, step))
+                    .collect(),
+                utc_offset: 0,
+            })
+        })
+    }
+
+    fn latest_quote<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, anyhow::Result<Quote>> {
+        Box::pin(async move {
+            let step = Interval::OneDay.seconds() as i64;
+            let now = OffsetDateTime::now_utc().unix_timestamp();
+
+            Ok(Self::bar(symbol, now / step * step, step))
+        })
+    }
+}
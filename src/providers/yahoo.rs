@@ -0,0 +1,52 @@
+use futures::future::BoxFuture;
+use yahoo_finance_api::{time::OffsetDateTime, Quote, YQuoteItem, YahooConnector};
+
+use super::{History, MarketDataProvider};
+use crate::interval::Interval;
+
+#[derive(Default)]
+pub struct YahooProvider {
+    connector: YahooConnector,
+}
+
+impl MarketDataProvider for YahooProvider {
+    fn name(&self) -> &str {
+        "yahoo"
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<YQuoteItem>>> {
+        Box::pin(async move {
+            let response = self.connector.search_ticker(query).await?;
+
+            Ok(response.quotes)
+        })
+    }
+
+    fn history<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> BoxFuture<'a, anyhow::Result<History>> {
+        Box::pin(async move {
+            let response = self
+                .connector
+                .get_quote_history_interval(symbol, start, end, interval.as_str())
+                .await?;
+
+            Ok(History {
+                quotes: response.quotes()?,
+                utc_offset: response.metadata()?.gmtoffset,
+            })
+        })
+    }
+
+    fn latest_quote<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, anyhow::Result<Quote>> {
+        Box::pin(async move {
+            let response = self.connector.get_latest_quotes(symbol, "1d").await?;
+
+            Ok(response.last_quote()?)
+        })
+    }
+}
@@ -1,36 +1,433 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
-use yahoo_finance_api::Quote;
-use yahoo_finance_api::{time::OffsetDateTime, YQuoteItem};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use rand::Rng;
+use yahoo_finance_api::{time::OffsetDateTime, Quote, YQuoteItem};
 
-use crate::app::CONNECTOR;
+use crate::{
+    error::DataError,
+    interval::Interval,
+    providers::{History, MarketDataProvider},
+};
 
-pub async fn search(symbol: &str) -> anyhow::Result<Vec<YQuoteItem>> {
-    let response = CONNECTOR.search_ticker(symbol).await;
+/// Number of finished requests kept in a [`RequestLog`].
+const LOG_LENGTH: usize = 200;
 
-    Ok(response?.quotes)
+pub async fn search(
+    provider: &dyn MarketDataProvider,
+    symbol: &str,
+) -> Result<Vec<YQuoteItem>, DataError> {
+    provider.search(symbol).await.map_err(|e| {
+        log::error!("Error searching for {}: {:#}", symbol, e);
+        DataError::from(e)
+    })
 }
 
+/// Bars between `start` and `end`, an empty answer is [`DataError::NoDataInRange`].
 pub async fn get_history(
-    symbol: String,
-    selected_symbol_history: Arc<Mutex<Option<Vec<Quote>>>>,
+    provider: &dyn MarketDataProvider,
+    symbol: &str,
     start: OffsetDateTime,
     end: OffsetDateTime,
-) {
-    match CONNECTOR.get_quote_history(&symbol, start, end).await {
-        Ok(history) => {
-            let quotes = match history.quotes() {
-                Ok(quotes) => quotes,
-                Err(e) => {
-                    eprintln!("Error: {}", e);
+    interval: Interval,
+) -> Result<History, DataError> {
+    let error = match provider.history(symbol, start, end, interval).await {
+        Ok(history) if !history.quotes.is_empty() => return Ok(history),
+        Ok(_) => DataError::NoDataInRange,
+        Err(e) => DataError::from(e),
+    };
+
+    log::error!(
+        "Error fetching {} quotes for {}: {}",
+        interval,
+        symbol,
+        error
+    );
+
+    Err(error)
+}
+
+/// Limits, retries and timeout of the requests to one provider.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RequestPolicy {
+    /// Requests per second in the long run.
+    pub rate: f64,
+    /// Requests that may be sent at once after a quiet period.
+    pub burst: f64,
+    /// Attempts after the first one on transient errors.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for every further one.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    /// Time a single attempt may take.
+    pub timeout: Duration,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        RequestPolicy {
+            rate: 2.0,
+            burst: 5.0,
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+impl RequestPolicy {
+    /// Exponential backoff with jitter, between half and all of the full delay.
+    fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Succeeded,
+    Failed(String),
+    TimedOut,
+    /// Answered by an identical request that was already in flight.
+    Joined,
+}
+
+/// A finished request as shown in the UI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestRecord {
+    pub finished: SystemTime,
+    pub provider: String,
+    pub description: String,
+    pub attempts: u32,
+    pub elapsed: Duration,
+    pub outcome: Outcome,
+}
+
+/// The latest finished requests, shared between the request layers and the UI.
+#[derive(Clone, Default)]
+pub struct RequestLog {
+    records: Arc<Mutex<VecDeque<RequestRecord>>>,
+}
+
+impl RequestLog {
+    fn push(&self, record: RequestRecord) {
+        let mut records = self.records.lock().unwrap();
+
+        if records.len() == LOG_LENGTH {
+            records.pop_front();
+        }
+
+        records.push_back(record);
+    }
+
+    /// Oldest first.
+    pub fn records(&self) -> Vec<RequestRecord> {
+        self.records.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+type SharedHistory = Shared<BoxFuture<'static, Result<History, Arc<anyhow::Error>>>>;
+
+/// A history request in flight and the callers waiting for it.
+struct InFlight {
+    /// Identifies the request, a newer one for the series may replace it.
+    id: u64,
+    start: OffsetDateTime,
+    future: SharedHistory,
+    waiters: usize,
+}
+
+type InFlightMap = Mutex<HashMap<(String, Interval), InFlight>>;
+
+/// Held by every caller waiting for an in-flight request. The entry goes
+/// away once the request finished or the last caller gave up on it, so later
+/// requests never join a stale or abandoned one.
+struct Waiter<'a> {
+    in_flight: &'a InFlightMap,
+    key: (String, Interval),
+    id: u64,
+    finished: bool,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+
+        let Some(entry) = in_flight
+            .get_mut(&self.key)
+            .filter(|entry| entry.id == self.id)
+        else {
+            return;
+        };
+
+        entry.waiters -= 1;
+
+        if self.finished || entry.waiters == 0 {
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+/// Part of a [`RequestLayer`] that its in-flight requests own.
+struct Limiter {
+    provider: Box<dyn MarketDataProvider>,
+    policy: RequestPolicy,
+    bucket: Mutex<TokenBucket>,
+    log: RequestLog,
+}
+
+impl Limiter {
+    /// Waits until the token bucket allows another request.
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+
+                bucket.tokens = (bucket.tokens
+                    + now.duration_since(bucket.updated).as_secs_f64() * self.policy.rate)
+                    .min(self.policy.burst);
+                bucket.updated = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
                     return;
                 }
+
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.policy.rate)
             };
 
-            *selected_symbol_history.lock().unwrap() = Some(quotes);
+            tokio::time::sleep(wait).await;
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
+    }
+
+    /// Sends `request` within the rate limit, retrying transient failures.
+    async fn call<'a, T>(
+        &'a self,
+        description: String,
+        request: impl Fn() -> BoxFuture<'a, anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        let started = Instant::now();
+        let mut attempts = 0;
+
+        let (result, outcome) = loop {
+            self.acquire().await;
+
+            attempts += 1;
+
+            let (result, outcome, transient) =
+                match tokio::time::timeout(self.policy.timeout, request()).await {
+                    Ok(Ok(value)) => (Ok(value), Outcome::Succeeded, false),
+                    Ok(Err(e)) => {
+                        let error = DataError::from(&e);
+                        let transient = error.is_transient();
+
+                        (Err(e), Outcome::Failed(error.to_string()), transient)
+                    }
+                    Err(_) => (
+                        Err(DataError::Network(format!(
+                            "No answer within {} s",
+                            self.policy.timeout.as_secs_f64()
+                        ))
+                        .into()),
+                        Outcome::TimedOut,
+                        true,
+                    ),
+                };
+
+            if !transient || attempts > self.policy.retries {
+                break (result, outcome);
+            }
+
+            let delay = self.policy.backoff(attempts - 1);
+
+            log::warn!(
+                "{} {} failed ({:?}), retrying in {:.1} s",
+                self.provider.name(),
+                description,
+                outcome,
+                delay.as_secs_f64()
+            );
+
+            tokio::time::sleep(delay).await;
+        };
+
+        self.log.push(RequestRecord {
+            finished: SystemTime::now(),
+            provider: self.provider.name().to_string(),
+            description,
+            attempts,
+            elapsed: started.elapsed(),
+            outcome,
+        });
+
+        result
+    }
+}
+
+/// Wraps a provider with a token-bucket rate limit, retries with backoff on
+/// transient errors, a timeout per attempt and deduplication of identical
+/// history requests in flight. Outcomes go to a [`RequestLog`].
+pub struct RequestLayer {
+    limiter: Arc<Limiter>,
+    /// In-flight history requests by series.
+    in_flight: InFlightMap,
+    next_id: AtomicU64,
+}
+
+impl RequestLayer {
+    pub fn new(
+        provider: Box<dyn MarketDataProvider>,
+        policy: RequestPolicy,
+        log: RequestLog,
+    ) -> Self {
+        RequestLayer {
+            limiter: Arc::new(Limiter {
+                provider,
+                bucket: Mutex::new(TokenBucket {
+                    tokens: policy.burst,
+                    updated: Instant::now(),
+                }),
+                policy,
+                log,
+            }),
+            in_flight: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+}
+
+impl RequestLayer {
+    /// A history request that other callers can join while it runs.
+    fn fetch(
+        &self,
+        symbol: &str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> SharedHistory {
+        let limiter = self.limiter.clone();
+        let symbol = symbol.to_string();
+
+        async move {
+            limiter
+                .call(format!("history {} {}", symbol, interval), || {
+                    limiter.provider.history(&symbol, start, end, interval)
+                })
+                .await
+                .map_err(Arc::new)
         }
+        .boxed()
+        .shared()
+    }
+}
+
+impl MarketDataProvider for RequestLayer {
+    fn name(&self) -> &str {
+        self.limiter.provider.name()
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<YQuoteItem>>> {
+        Box::pin(async move {
+            self.limiter
+                .call(format!("search {}", query), || {
+                    self.limiter.provider.search(query)
+                })
+                .await
+        })
+    }
+
+    fn history<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> BoxFuture<'a, anyhow::Result<History>> {
+        Box::pin(async move {
+            let key = (symbol.to_string(), interval);
+
+            // A request already covering the range answers this one as well.
+            let (future, id, joined) = {
+                let mut in_flight = self.in_flight.lock().unwrap();
+
+                match in_flight.get_mut(&key).filter(|entry| entry.start <= start) {
+                    Some(entry) => {
+                        entry.waiters += 1;
+
+                        (entry.future.clone(), entry.id, true)
+                    }
+                    None => {
+                        let future = self.fetch(symbol, start, end, interval);
+                        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+                        in_flight.insert(
+                            key.clone(),
+                            InFlight {
+                                id,
+                                start,
+                                future: future.clone(),
+                                waiters: 1,
+                            },
+                        );
+
+                        (future, id, false)
+                    }
+                }
+            };
+
+            let mut waiter = Waiter {
+                in_flight: &self.in_flight,
+                key,
+                id,
+                finished: false,
+            };
+
+            let started = Instant::now();
+            let result = future.await;
+
+            waiter.finished = true;
+            drop(waiter);
+
+            if joined {
+                self.limiter.log.push(RequestRecord {
+                    finished: SystemTime::now(),
+                    provider: self.name().to_string(),
+                    description: format!("history {} {}", symbol, interval),
+                    attempts: 0,
+                    elapsed: started.elapsed(),
+                    outcome: Outcome::Joined,
+                });
+            }
+
+            result.map_err(|e| DataError::from(e.as_ref()).into())
+        })
+    }
+
+    fn latest_quote<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, anyhow::Result<Quote>> {
+        Box::pin(async move {
+            self.limiter
+                .call(format!("latest quote {}", symbol), || {
+                    self.limiter.provider.latest_quote(symbol)
+                })
+                .await
+        })
     }
 }
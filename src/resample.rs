@@ -0,0 +1,107 @@
+use chrono::{DateTime, Datelike, FixedOffset};
+use yahoo_finance_api::Quote;
+
+/// Size of the buckets quotes are aggregated into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bucket {
+    Minutes(u32),
+    Hours(u32),
+    Days(u32),
+    /// Weeks start on Monday.
+    Weeks(u32),
+    Months(u32),
+    Quarters(u32),
+}
+
+impl Bucket {
+    /// Identifies the bucket a timestamp falls into, in the local time of the
+    /// exchange. Equal keys mean the same bucket.
+    fn key(&self, timestamp: u64, utc_offset: FixedOffset) -> i64 {
+        let local = DateTime::from_timestamp(timestamp as i64, 0)
+            .unwrap_or_default()
+            .with_timezone(&utc_offset);
+
+        let seconds = local.naive_local().and_utc().timestamp();
+        let days = seconds.div_euclid(24 * 60 * 60);
+        let months = local.year() as i64 * 12 + local.month0() as i64;
+
+        match *self {
+            Bucket::Minutes(n) => seconds.div_euclid(60 * n.max(1) as i64),
+            Bucket::Hours(n) => seconds.div_euclid(3600 * n.max(1) as i64),
+            Bucket::Days(n) => days.div_euclid(n.max(1) as i64),
+            // 1970-01-01 was a Thursday, shift so buckets begin on Monday.
+            Bucket::Weeks(n) => (days + 3).div_euclid(7 * n.max(1) as i64),
+            Bucket::Months(n) => months.div_euclid(n.max(1) as i64),
+            Bucket::Quarters(n) => months.div_euclid(3 * n.max(1) as i64),
+        }
+    }
+}
+
+/// Aggregates quotes sorted by timestamp into larger buckets.
+///
+/// Each bucket takes the open of its first quote, the close and adjclose of
+/// its last one, the extreme high and low and the summed volume. Its timestamp
+/// is the one of its first quote. Bucket boundaries are computed in the time
+/// zone given by `utc_offset`, usually the exchange's.
+///
+/// The offset is fixed, the one providers report for the current date, so
+/// it is an hour off for bars on the other side of a daylight saving change.
+/// Shifting every bar of a session by an hour keeps them together in minute
+/// buckets that divide an hour and in single hour buckets. Minute buckets
+/// that don't divide an hour (e.g. 90 or 240) and buckets of several hours
+/// can split differently across the change, and intraday bars within an
+/// hour of local midnight can land on the neighbouring day, week, month or
+/// quarter. Daily bars are stamped at the session open or midnight UTC,
+/// well away from local midnight, so they are not affected.
+pub fn resample(quotes: &[Quote], bucket: Bucket, utc_offset: FixedOffset) -> Vec<Quote> {
+    let mut resampled: Vec<Quote> = Vec::new();
+    let mut current_key = None;
+
+    for quote in quotes {
+        let key = bucket.key(quote.timestamp, utc_offset);
+
+        match resampled.last_mut() {
+            Some(last) if current_key == Some(key) => {
+                last.high = last.high.max(quote.high);
+                last.low = last.low.min(quote.low);
+                last.close = quote.close;
+                last.adjclose = quote.adjclose;
+                last.volume += quote.volume;
+            }
+            _ => {
+                resampled.push(quote.clone());
+                current_key = Some(key);
+            }
+        }
+    }
+
+    resampled
+}
+
+/// Heikin-Ashi bars of quotes sorted by timestamp.
+///
+/// The close is the average of the bar's open, high, low and close, the open
+/// the middle of the previous Heikin-Ashi bar's body, and the high and low
+/// also cover the new open and close. Timestamps and volumes are kept.
+pub fn heikin_ashi(quotes: &[Quote]) -> Vec<Quote> {
+    let mut bars: Vec<Quote> = Vec::with_capacity(quotes.len());
+
+    for quote in quotes {
+        let close = (quote.open + quote.high + quote.low + quote.close) / 4.0;
+        let open = match bars.last() {
+            Some(previous) => (previous.open + previous.close) / 2.0,
+            None => (quote.open + quote.close) / 2.0,
+        };
+
+        bars.push(Quote {
+            open,
+            high: quote.high.max(open).max(close),
+            low: quote.low.min(open).min(close),
+            close,
+            adjclose: close,
+            ..quote.clone()
+        });
+    }
+
+    bars
+}
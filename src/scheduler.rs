@@ -0,0 +1,340 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Datelike, FixedOffset, Timelike, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::DataError, interval::Interval, storage::Snapshot, Storage};
+
+/// How often the scheduler looks for due series.
+const TICK: Duration = Duration::from_secs(1);
+
+/// Regular session of an exchange, Monday to Friday.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradingHours {
+    /// Minutes after midnight in the exchange's time zone.
+    pub open: u32,
+    pub close: u32,
+}
+
+impl Default for TradingHours {
+    fn default() -> Self {
+        TradingHours {
+            open: 9 * 60 + 30,
+            close: 16 * 60,
+        }
+    }
+}
+
+impl TradingHours {
+    pub fn is_open(&self, now: SystemTime, utc_offset: i32) -> bool {
+        let local = local_time(now, utc_offset);
+        let minute = local.hour() * 60 + local.minute();
+
+        is_trading_day(local.weekday()) && (self.open..self.close).contains(&minute)
+    }
+
+    /// The latest close at or before `now`.
+    pub fn last_close(&self, now: SystemTime, utc_offset: i32) -> Option<SystemTime> {
+        let local = local_time(now, utc_offset);
+
+        (0..7)
+            .filter_map(|days| local.date_naive().checked_sub_days(chrono::Days::new(days)))
+            .filter(|date| is_trading_day(date.weekday()))
+            .filter_map(|date| {
+                let close = date.and_hms_opt(self.close / 60, self.close % 60, 0)?;
+
+                close.and_local_timezone(*local.offset()).single()
+            })
+            .find(|close| *close <= local)
+            .map(SystemTime::from)
+    }
+}
+
+fn local_time(time: SystemTime, utc_offset: i32) -> DateTime<FixedOffset> {
+    let offset = FixedOffset::east_opt(utc_offset).unwrap_or(FixedOffset::east_opt(0).unwrap());
+
+    DateTime::<Utc>::from(time).with_timezone(&offset)
+}
+
+fn is_trading_day(weekday: Weekday) -> bool {
+    !matches!(weekday, Weekday::Sat | Weekday::Sun)
+}
+
+/// When the scheduler refreshes series.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RefreshSettings {
+    pub enabled: bool,
+    /// Seconds between two refreshes of a fetched interval.
+    pub cadence: BTreeMap<Interval, u64>,
+    /// Intraday series wait for the session, daily series are only refreshed
+    /// once after the close.
+    pub pause_outside_hours: bool,
+    pub hours: TradingHours,
+}
+
+impl Default for RefreshSettings {
+    fn default() -> Self {
+        RefreshSettings {
+            enabled: true,
+            cadence: BTreeMap::from([
+                (Interval::OneMinute, 60),
+                (Interval::FiveMinutes, 5 * 60),
+                (Interval::OneHour, 15 * 60),
+                (Interval::OneDay, 60 * 60),
+            ]),
+            pause_outside_hours: true,
+            hours: TradingHours::default(),
+        }
+    }
+}
+
+impl RefreshSettings {
+    /// Intervals that are fetched, all others are resampled from these.
+    pub fn fetched_intervals() -> impl Iterator<Item = Interval> {
+        Interval::ALL
+            .into_iter()
+            .filter(|interval| interval.source().0 == *interval)
+    }
+
+    pub fn cadence(&self, interval: Interval) -> Duration {
+        self.cadence
+            .get(&interval)
+            .map(|seconds| Duration::from_secs(*seconds))
+            .unwrap_or(interval.update_interval())
+    }
+
+    /// Next refresh of a series, `None` while it is paused.
+    fn next_run(
+        &self,
+        interval: Interval,
+        last_run: Option<SystemTime>,
+        now: SystemTime,
+        utc_offset: i32,
+    ) -> Option<SystemTime> {
+        let cadence_due = last_run.map_or(now, |last_run| last_run + self.cadence(interval));
+
+        if !self.pause_outside_hours || self.hours.is_open(now, utc_offset) {
+            return Some(cadence_due);
+        }
+
+        if interval.is_intraday() {
+            return None;
+        }
+
+        let last_close = self.hours.last_close(now, utc_offset)?;
+
+        last_run
+            .is_none_or(|last_run| last_run < last_close)
+            .then_some(now)
+    }
+}
+
+/// A refreshed series as shown in the status panel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduleEntry {
+    pub symbol: String,
+    /// Always a fetched interval.
+    pub interval: Interval,
+    pub last_run: Option<SystemTime>,
+    /// `None` while paused outside trading hours.
+    pub next_run: Option<SystemTime>,
+    pub running: bool,
+    /// Whether the last run fetched quotes, they are skipped while fresh.
+    pub fetched: bool,
+    /// Why the last run failed.
+    pub error: Option<DataError>,
+}
+
+struct SchedulerState {
+    settings: RefreshSettings,
+    entries: BTreeMap<(String, Interval), ScheduleEntry>,
+    /// Runs every series on the next tick regardless of the settings.
+    forced: bool,
+}
+
+impl SchedulerState {
+    fn plan(&mut self, snapshot: &Snapshot, now: SystemTime) {
+        for entry in self.entries.values_mut() {
+            entry.next_run = match self.forced {
+                true => Some(now),
+                false => self.settings.next_run(
+                    entry.interval,
+                    entry.last_run,
+                    now,
+                    snapshot.utc_offset(&entry.symbol),
+                ),
+            };
+        }
+    }
+
+    fn finish(
+        &mut self,
+        symbol: String,
+        interval: Interval,
+        started: SystemTime,
+        result: Result<bool, DataError>,
+        utc_offset: i32,
+    ) {
+        let Some(entry) = self.entries.get_mut(&(symbol, interval)) else {
+            return;
+        };
+
+        entry.running = false;
+        entry.fetched = result.as_ref().is_ok_and(|fetched| *fetched);
+        entry.error = result.err();
+        entry.last_run = Some(started);
+        entry.next_run =
+            self.settings
+                .next_run(interval, entry.last_run, SystemTime::now(), utc_offset);
+    }
+
+    /// Marks the due entries as running and returns them with the age their
+    /// quotes may have.
+    fn take_due(&mut self, now: SystemTime) -> Vec<(String, Interval, Duration)> {
+        if !std::mem::take(&mut self.forced) && !self.settings.enabled {
+            return Vec::new();
+        }
+
+        self.entries
+            .values_mut()
+            .filter(|entry| !entry.running && entry.next_run.is_some_and(|next| next <= now))
+            .map(|entry| {
+                entry.running = true;
+
+                // The first run keeps quotes that are already fresh in the cache.
+                let max_age = match entry.last_run {
+                    Some(_) => Duration::ZERO,
+                    None => self.settings.cadence(entry.interval),
+                };
+
+                (entry.symbol.clone(), entry.interval, max_age)
+            })
+            .collect()
+    }
+}
+
+/// Refreshes the series of charts, watchlists, the portfolio and alerts in
+/// the background.
+#[derive(Clone)]
+pub struct Scheduler {
+    storage: Storage,
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl Scheduler {
+    pub fn new(storage: Storage, settings: RefreshSettings) -> Self {
+        Scheduler {
+            storage,
+            state: Arc::new(Mutex::new(SchedulerState {
+                settings,
+                entries: BTreeMap::new(),
+                forced: false,
+            })),
+        }
+    }
+
+    /// Runs the scheduler on the storage's runtime until it is dropped.
+    pub fn start(&self) {
+        let storage = self.storage.clone();
+        let state = Arc::downgrade(&self.state);
+
+        self.storage.runtime().spawn(async move {
+            let mut ticks = tokio::time::interval(TICK);
+
+            loop {
+                ticks.tick().await;
+
+                let Some(state) = state.upgrade() else {
+                    break;
+                };
+
+                let now = SystemTime::now();
+
+                let due = {
+                    let mut state = state.lock().unwrap();
+
+                    state.plan(&storage.snapshot(), now);
+                    state.take_due(now)
+                };
+
+                for (symbol, interval, max_age) in due {
+                    let storage = storage.clone();
+                    let state = state.clone();
+
+                    tokio::spawn(async move {
+                        log::debug!("Scheduled refresh of {} {}", symbol, interval);
+
+                        let result = storage.update(&symbol, interval, max_age).await;
+
+                        let utc_offset = storage.snapshot().utc_offset(&symbol);
+
+                        state
+                            .lock()
+                            .unwrap()
+                            .finish(symbol, interval, now, result, utc_offset);
+                    });
+                }
+            }
+        });
+    }
+
+    /// Replaces the refreshed series, resampled intervals map to their source.
+    pub fn set_targets(&self, targets: impl IntoIterator<Item = (String, Interval)>) {
+        let targets = targets
+            .into_iter()
+            .map(|(symbol, interval)| (symbol, interval.source().0))
+            .collect::<BTreeSet<_>>();
+
+        let mut state = self.state.lock().unwrap();
+
+        state.entries.retain(|key, _| targets.contains(key));
+
+        for (symbol, interval) in targets {
+            state
+                .entries
+                .entry((symbol.clone(), interval))
+                .or_insert(ScheduleEntry {
+                    symbol,
+                    interval,
+                    last_run: None,
+                    next_run: Some(SystemTime::now()),
+                    running: false,
+                    fetched: false,
+                    error: None,
+                });
+        }
+    }
+
+    pub fn settings(&self) -> RefreshSettings {
+        self.state.lock().unwrap().settings.clone()
+    }
+
+    pub fn set_settings(&self, settings: RefreshSettings) {
+        self.state.lock().unwrap().settings = settings;
+    }
+
+    /// Refreshes every series on the next tick, even outside trading hours.
+    pub fn refresh_now(&self) {
+        self.state.lock().unwrap().forced = true;
+    }
+
+    /// The series ordered by their next run, paused ones last.
+    pub fn queue(&self) -> Vec<ScheduleEntry> {
+        let mut queue = self
+            .state
+            .lock()
+            .unwrap()
+            .entries
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        queue.sort_by_key(|entry| (entry.next_run.is_none(), entry.next_run));
+
+        queue
+    }
+}
@@ -1,145 +1,586 @@
 use std::{
-    future::Future,
-    pin::Pin,
-    {sync::Arc, sync::Mutex},
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use egui::ahash::HashMap;
+use tokio::sync::watch;
+use yahoo_finance_api::{time::OffsetDateTime, Quote, YQuoteItem};
+
+use crate::{
+    alerts::AlertBook,
+    cache::QuotesCache,
+    error::{DataError, ErrorLog},
+    interval::Interval,
+    providers::{History, MarketDataProvider},
+    requests::{get_history, search},
+    resample::resample,
+};
 
-use yahoo_finance_api::Quote;
+/// Shared handle to the quotes and alerts, cheap to clone.
+///
+/// Fetches go through `provider` and run on `runtime`. Readers get an
+/// immutable [`Snapshot`] and never wait for a fetch, the write lock is only
+/// held while merging and never across an await.
+#[derive(Clone)]
+pub struct Storage {
+    inner: Arc<Mutex<StorageInner>>,
+    snapshot: Arc<RwLock<Arc<Snapshot>>>,
+    /// Carries the snapshot version to subscribers of a series.
+    watchers: Arc<Mutex<HashMap<SeriesKey, watch::Sender<u64>>>>,
+    provider: Arc<dyn MarketDataProvider>,
+    runtime: tokio::runtime::Handle,
+    errors: ErrorLog,
+    /// Snapshot version of the alerts last written to disk.
+    alerts_saved: Arc<Mutex<u64>>,
+}
 
-use crate::{app::RUNTIME, requests::get_history};
+impl Storage {
+    pub fn new(
+        provider: Arc<dyn MarketDataProvider>,
+        runtime: tokio::runtime::Handle,
+        quotes: QuotesStorage,
+        alerts: AlertBook,
+    ) -> Self {
+        let inner = StorageInner {
+            quotes,
+            alerts,
+            errors: HashMap::new(),
+            version: 0,
+        };
+
+        Storage {
+            snapshot: Arc::new(RwLock::new(Arc::new(inner.snapshot()))),
+            inner: Arc::new(Mutex::new(inner)),
+            watchers: Arc::default(),
+            provider,
+            runtime,
+            errors: ErrorLog::default(),
+            alerts_saved: Arc::default(),
+        }
+    }
 
-#[derive(Default, Clone)]
-pub struct Storage(Arc<tokio::sync::Mutex<StorageInner>>);
+    /// Storage backed by the on-disk quote cache, pre-filled with its contents.
+    pub fn load(provider: Arc<dyn MarketDataProvider>, runtime: tokio::runtime::Handle) -> Self {
+        let quotes = match QuotesCache::in_data_dir() {
+            Some(cache) => QuotesStorage::with_cache(cache),
+            None => {
+                log::warn!("No data directory found, quotes will not be cached");
+                QuotesStorage::default()
+            }
+        };
 
-impl Storage {
-    pub fn access(
+        Storage::new(provider, runtime, quotes, AlertBook::load())
+    }
+
+    pub fn provider(&self) -> &Arc<dyn MarketDataProvider> {
+        &self.provider
+    }
+
+    pub fn runtime(&self) -> &tokio::runtime::Handle {
+        &self.runtime
+    }
+
+    /// The latest published state, never waits for a running fetch.
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        self.snapshot.read().unwrap().clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StorageInner> {
+        self.inner.lock().unwrap()
+    }
+
+    /// Replaces the snapshot with the current state.
+    fn publish(&self, inner: &mut StorageInner) {
+        inner.version += 1;
+
+        *self.snapshot.write().unwrap() = Arc::new(inner.snapshot());
+    }
+
+    /// Fetches quotes older than `max_age` and checks the alerts on them.
+    ///
+    /// Returns whether quotes were fetched. The storage stays readable and
+    /// writable while the provider is waited on. A failure is kept in the
+    /// snapshot until the series loads again and goes to the error log.
+    pub async fn update(
         &self,
-        function: impl FnOnce(Self) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + 'static,
-    ) {
-        let storage = self.clone();
+        symbol: &str,
+        interval: Interval,
+        max_age: Duration,
+    ) -> Result<bool, DataError> {
+        let key = (symbol.to_string(), interval);
+
+        let (range, previous_update) = {
+            let mut inner = self.lock();
+            let previous_update = inner.quotes.last_update.get(&key).copied();
+
+            (
+                inner.quotes.request(symbol, interval, max_age),
+                previous_update,
+            )
+        };
+
+        let Some((start, end)) = range else {
+            log::debug!("Skipping update for {} {}", symbol, interval);
+            return Ok(false);
+        };
+
+        let result = get_history(self.provider.as_ref(), symbol, start, end, interval).await;
+
+        let (version, persist, alerts, result) = {
+            let mut inner = self.lock();
+
+            let (persist, alerts, result) = match result {
+                Ok(history) => {
+                    inner.quotes.merge(symbol, interval, history);
+                    inner.errors.remove(&key);
+
+                    let alerts = inner
+                        .evaluate_alerts(symbol, interval)
+                        .then(|| inner.alerts.clone());
+
+                    (inner.quotes.persist(symbol, interval), alerts, Ok(true))
+                }
+                Err(error) => {
+                    inner.quotes.restore_update(key.clone(), previous_update);
+                    inner.errors.insert(key, error.clone());
 
-        RUNTIME.spawn(async move {
-            let _ = Box::pin(function(storage).await);
-        });
+                    (None, None, Err(error))
+                }
+            };
+
+            self.publish(&mut inner);
+
+            (inner.version, persist, alerts, result)
+        };
+
+        self.notify(symbol, interval, version);
+
+        // The file is synced to disk, keep that off the runtime's workers.
+        if let Some(persist) = persist {
+            if let Err(e) = self.runtime.spawn_blocking(persist).await {
+                log::error!("Error caching {} quotes for {}: {}", interval, symbol, e);
+            }
+        }
+
+        if let Some(alerts) = alerts {
+            self.save_alerts(version, alerts);
+        }
+
+        if let Err(error) = &result {
+            self.errors
+                .push(format!("{} {}", symbol, interval), error.clone());
+        }
+
+        result
     }
 
-    pub fn update_quotes_checked(&self, symbol: &str) {
-        let symbol = symbol.to_string();
+    /// Searches the provider, failures go to the error log.
+    pub async fn search(&self, query: &str) -> Result<Vec<YQuoteItem>, DataError> {
+        let result = search(self.provider.as_ref(), query).await;
+
+        if let Err(error) = &result {
+            self.errors
+                .push(format!("Search for \"{}\"", query), error.clone());
+        }
+
+        result
+    }
+
+    /// Fetches the latest quote of `symbol`, failures go to the error log.
+    pub async fn latest_quote(&self, symbol: &str) -> Result<Quote, DataError> {
+        let result = self
+            .provider
+            .latest_quote(symbol)
+            .await
+            .map_err(DataError::from);
+
+        if let Err(error) = &result {
+            self.errors
+                .push(format!("Latest quote of {}", symbol), error.clone());
+        }
+
+        result
+    }
 
-        self.access(move |storage| {
-            Box::pin(async move {
-                let mut storage = storage.inner().await;
+    /// Recent failed loads, searches and latest quotes.
+    pub fn errors(&self) -> &ErrorLog {
+        &self.errors
+    }
 
-                storage.quotes.update_checked(&symbol).await;
-            })
+    /// Calls `on_change` from the runtime whenever new bars of the stored
+    /// series arrive, until the subscription is dropped.
+    pub fn subscribe(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        on_change: impl Fn() + Send + 'static,
+    ) -> Subscription {
+        let receiver = self
+            .watchers
+            .lock()
+            .unwrap()
+            .entry((symbol.to_string(), interval))
+            .or_insert_with(|| watch::channel(0).0)
+            .subscribe();
+
+        let mut changes = receiver.clone();
+
+        let task = self.runtime.spawn(async move {
+            while changes.changed().await.is_ok() {
+                on_change();
+            }
         });
+
+        Subscription {
+            receiver,
+            task: task.abort_handle(),
+        }
     }
 
-    pub async fn inner(&self) -> tokio::sync::MutexGuard<StorageInner> {
-        self.0.lock().await
+    fn notify(&self, symbol: &str, interval: Interval, version: u64) {
+        let mut watchers = self.watchers.lock().unwrap();
+
+        watchers.retain(|_, sender| sender.receiver_count() > 0);
+
+        if let Some(sender) = watchers.get(&(symbol.to_string(), interval)) {
+            sender.send_replace(version);
+        }
     }
 
-    #[allow(dead_code)]
-    pub fn inner_blocking(&self) -> tokio::sync::MutexGuard<StorageInner> {
-        self.0.blocking_lock()
+    pub fn update_quotes_checked(&self, symbol: &str, interval: Interval) {
+        self.refresh_quotes(symbol, interval, interval.update_interval());
     }
 
-    pub fn get_quotes(&self, symbol: &str) -> Option<Vec<Quote>> {
+    /// Like [`Storage::update_quotes_checked`] but refetches quotes older than `max_age`.
+    pub fn refresh_quotes(&self, symbol: &str, interval: Interval, max_age: Duration) {
         let storage = self.clone();
         let symbol = symbol.to_string();
 
-        let history = Arc::new(Mutex::new(None));
+        self.runtime.spawn(async move {
+            let _ = storage.update(&symbol, interval, max_age).await;
+        });
+    }
 
-        let cloned_history = history.clone();
+    pub fn alerts(&self) -> Arc<AlertBook> {
+        self.snapshot().alerts.clone()
+    }
 
-        RUNTIME.block_on(async {
-            let storage = storage.inner().await;
+    /// Changes the alerts, they are written to disk in the background.
+    pub fn modify_alerts(&self, function: impl FnOnce(&mut AlertBook)) {
+        let (version, alerts) = {
+            let mut inner = self.lock();
 
-            let history = storage.quotes.history.get(&symbol).cloned();
+            function(&mut inner.alerts);
 
-            *cloned_history.lock().unwrap() = history;
+            self.publish(&mut inner);
+
+            (inner.version, inner.alerts.clone())
+        };
+
+        self.save_alerts(version, alerts);
+    }
+
+    /// Writes the alerts of snapshot `version` on a blocking thread, unless
+    /// a newer version was written already.
+    fn save_alerts(&self, version: u64, alerts: AlertBook) {
+        let saved = self.alerts_saved.clone();
+
+        self.runtime.spawn_blocking(move || {
+            let mut saved = saved.lock().unwrap();
+
+            if *saved < version {
+                alerts.save();
+                *saved = version;
+            }
         });
+    }
 
-        let mut history = history.lock().unwrap();
+    /// Stored bars of `interval` without resampling.
+    pub fn get_quotes(&self, symbol: &str, interval: Interval) -> Option<Vec<Quote>> {
+        self.snapshot()
+            .quotes(symbol, interval)
+            .map(|quotes| quotes.to_vec())
+    }
 
-        match history.take() {
-            Some(history) => Some(history),
-            None => None,
-        }
+    /// Offset of the exchange's time zone from UTC in seconds, 0 if unknown.
+    pub fn get_utc_offset(&self, symbol: &str) -> i32 {
+        self.snapshot().utc_offset(symbol)
+    }
+}
+
+/// Notifications about one stored series, see [`Storage::subscribe`].
+pub struct Subscription {
+    receiver: watch::Receiver<u64>,
+    task: tokio::task::AbortHandle,
+}
+
+impl Subscription {
+    /// Whether new bars arrived since the last call.
+    pub fn changed(&mut self) -> bool {
+        let changed = self.receiver.has_changed().unwrap_or(false);
+
+        self.receiver.borrow_and_update();
+
+        changed
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Immutable copy of the storage, the series are shared with the storage
+/// until they change.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    /// Incremented every time a new snapshot is published.
+    pub version: u64,
+    history: HashMap<SeriesKey, Arc<Vec<Quote>>>,
+    utc_offset: HashMap<String, i32>,
+    errors: HashMap<SeriesKey, DataError>,
+    pub alerts: Arc<AlertBook>,
+}
+
+impl Snapshot {
+    /// Stored bars of `interval` without resampling.
+    pub fn quotes(&self, symbol: &str, interval: Interval) -> Option<&Arc<Vec<Quote>>> {
+        self.history.get(&(symbol.to_string(), interval))
+    }
+
+    /// Quotes of `interval`, resampled from the stored source interval if needed.
+    pub fn series(&self, symbol: &str, interval: Interval) -> Option<Vec<Quote>> {
+        series(&self.history, &self.utc_offset, symbol, interval)
+    }
+
+    /// Offset of the exchange's time zone from UTC in seconds, 0 if unknown.
+    pub fn utc_offset(&self, symbol: &str) -> i32 {
+        self.utc_offset.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Why the last update of the series' source interval failed, if it did.
+    pub fn error(&self, symbol: &str, interval: Interval) -> Option<&DataError> {
+        self.errors.get(&(symbol.to_string(), interval.source().0))
     }
 }
 
-#[derive(Default)]
-pub struct StorageInner {
-    pub quotes: QuotesStorage,
+struct StorageInner {
+    quotes: QuotesStorage,
+    alerts: AlertBook,
+    /// Failure of the latest update of each series.
+    errors: HashMap<SeriesKey, DataError>,
+    version: u64,
 }
 
+impl StorageInner {
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            version: self.version,
+            history: self.quotes.history.clone(),
+            utc_offset: self.quotes.utc_offset.clone(),
+            errors: self.errors.clone(),
+            alerts: Arc::new(self.alerts.clone()),
+        }
+    }
+
+    /// Checks the alerts watching a series fetched in `interval`, returns
+    /// whether any triggered.
+    fn evaluate_alerts(&mut self, symbol: &str, interval: Interval) -> bool {
+        let mut triggered = false;
+
+        for (alert_symbol, alert_interval) in self.alerts.watched() {
+            if alert_symbol != symbol || alert_interval.source().0 != interval {
+                continue;
+            }
+
+            if let Some(quotes) = self.quotes.series(symbol, alert_interval) {
+                triggered |= self.alerts.evaluate(symbol, alert_interval, &quotes);
+            }
+        }
+
+        triggered
+    }
+}
+
+/// A series is identified by its symbol and bar interval.
+type SeriesKey = (String, Interval);
+
 #[derive(Default, Clone, Debug)]
 pub struct QuotesStorage {
-    history: HashMap<String, Vec<yahoo_finance_api::Quote>>,
-    last_update: HashMap<String, std::time::SystemTime>,
+    history: HashMap<SeriesKey, Arc<Vec<Quote>>>,
+    last_update: HashMap<SeriesKey, SystemTime>,
+    utc_offset: HashMap<String, i32>,
+    cache: Option<QuotesCache>,
 }
 
-const UPDATE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60 * 24);
-
 impl QuotesStorage {
-    pub async fn update_checked(&mut self, symbol: &str) {
-        let now = std::time::SystemTime::now();
+    pub fn with_cache(cache: QuotesCache) -> Self {
+        let mut storage = QuotesStorage::default();
+
+        for cached in cache.load_all() {
+            log::debug!(
+                "Loaded {} {} quotes for {}",
+                cached.quotes.len(),
+                cached.interval,
+                cached.symbol
+            );
+
+            storage
+                .utc_offset
+                .insert(cached.symbol.clone(), cached.utc_offset);
 
-        if let Some(last_update) = self.last_update.get(symbol) {
+            let key = (cached.symbol, cached.interval);
+
+            storage.last_update.insert(key.clone(), cached.last_update);
+            storage.history.insert(key, Arc::new(cached.quotes));
+        }
+
+        storage.cache = Some(cache);
+
+        storage
+    }
+
+    /// Time range to fetch if the series is older than `max_age`.
+    ///
+    /// Marks the series as updated, so concurrent requests for it are skipped
+    /// while this one is in flight.
+    pub fn request(
+        &mut self,
+        symbol: &str,
+        interval: Interval,
+        max_age: Duration,
+    ) -> Option<(OffsetDateTime, OffsetDateTime)> {
+        let now = SystemTime::now();
+        let key = (symbol.to_string(), interval);
+
+        if let Some(last_update) = self.last_update.get(&key) {
             if let Ok(elapsed) = now.duration_since(*last_update) {
-                if elapsed < UPDATE_INTERVAL {
-                    log::debug!("Skipping update for {}", symbol);
-                    return;
+                if elapsed < max_age {
+                    return None;
                 }
             }
         }
 
-        let last_update_for_symbol = self
-            .last_update
-            .get(symbol)
-            .cloned()
-            .unwrap_or(std::time::UNIX_EPOCH);
+        let last_update = self.last_update.insert(key.clone(), now);
+
+        // Refetch from the latest cached bar as well, it may still have been forming.
+        let start = last_update.unwrap_or(UNIX_EPOCH);
+        let start = match self.history.get(&key).and_then(|history| history.last()) {
+            Some(last_quote) => start.min(UNIX_EPOCH + Duration::from_secs(last_quote.timestamp)),
+            None => start,
+        };
+
+        // Intraday bars are only served for a limited time back.
+        let start = match interval.max_lookback() {
+            Some(max_lookback) => start.max(now - max_lookback),
+            None => start,
+        };
+
+        let end = OffsetDateTime::now_utc();
+        let since_start = now.duration_since(start).unwrap_or_default();
+
+        Some((
+            end.saturating_sub(yahoo_finance_api::time::Duration::seconds_f64(
+                since_start.as_secs_f64(),
+            )),
+            end,
+        ))
+    }
 
-        self.last_update.insert(symbol.to_string(), now);
+    /// Undoes the mark of a failed [`QuotesStorage::request`], so the next
+    /// request covers its range again.
+    fn restore_update(&mut self, key: SeriesKey, last_update: Option<SystemTime>) {
+        match last_update {
+            Some(last_update) => self.last_update.insert(key, last_update),
+            None => self.last_update.remove(&key),
+        };
+    }
 
-        self.update(symbol, last_update_for_symbol).await;
+    /// Merges a fetched history into the stored series.
+    pub fn merge(&mut self, symbol: &str, interval: Interval, history: History) {
+        let series = self
+            .history
+            .entry((symbol.to_string(), interval))
+            .or_default();
+
+        merge_quotes(Arc::make_mut(series), history.quotes, interval.seconds());
+
+        self.utc_offset
+            .insert(symbol.to_string(), history.utc_offset);
     }
 
-    pub async fn update(&mut self, symbol: &str, last_update: std::time::SystemTime) {
-        // TODO: make this nicer / extract
-        let end_datetime = yahoo_finance_api::time::OffsetDateTime::now_utc();
+    /// Quotes of `interval`, resampled from the stored source interval if needed.
+    pub fn series(&self, symbol: &str, interval: Interval) -> Option<Vec<Quote>> {
+        series(&self.history, &self.utc_offset, symbol, interval)
+    }
 
-        let duration_since_last_update =
-            match std::time::SystemTime::now().duration_since(last_update) {
-                Ok(duration) => duration,
-                Err(_) => {
-                    log::error!("Error getting duration since last update");
-                    return;
-                }
-            };
+    /// Writes a series to the cache when called, so it can run after the
+    /// storage lock is released.
+    fn persist(&self, symbol: &str, interval: Interval) -> Option<impl FnOnce() + Send + 'static> {
+        let key = (symbol.to_string(), interval);
+
+        let (Some(cache), Some(history), Some(last_update)) = (
+            self.cache.clone(),
+            self.history.get(&key).cloned(),
+            self.last_update.get(&key).copied(),
+        ) else {
+            return None;
+        };
+
+        let utc_offset = self.utc_offset.get(symbol).copied().unwrap_or_default();
+        let symbol = symbol.to_string();
+
+        Some(move || {
+            if let Err(e) = cache.save(&symbol, interval, utc_offset, last_update, &history) {
+                log::error!("Error caching {} quotes for {}: {:#}", interval, symbol, e);
+            }
+        })
+    }
+}
+
+fn series(
+    history: &HashMap<SeriesKey, Arc<Vec<Quote>>>,
+    utc_offset: &HashMap<String, i32>,
+    symbol: &str,
+    interval: Interval,
+) -> Option<Vec<Quote>> {
+    let (source, bucket) = interval.source();
+    let quotes = history.get(&(symbol.to_string(), source))?;
+
+    Some(match bucket {
+        Some(bucket) => {
+            let utc_offset =
+                chrono::FixedOffset::east_opt(utc_offset.get(symbol).copied().unwrap_or_default())
+                    .unwrap_or(chrono::FixedOffset::east_opt(0).unwrap());
+
+            resample(quotes, bucket, utc_offset)
+        }
+        None => quotes.to_vec(),
+    })
+}
 
-        let start_datetime =
-            end_datetime.saturating_sub(yahoo_finance_api::time::Duration::seconds_f64(
-                duration_since_last_update.as_secs_f64(),
-            ));
+/// Merges freshly fetched quotes into a series sorted by timestamp.
+///
+/// Cached bars from less than one bar before the first new bar on are
+/// replaced, which drops the previously still-forming bar even if the provider
+/// moved its timestamp. Duplicate timestamps keep the newest quote.
+pub fn merge_quotes(history: &mut Vec<Quote>, mut new: Vec<Quote>, bar_length: u64) {
+    new.sort_by_key(|quote| quote.timestamp);
 
-        let history = Arc::new(Mutex::new(None));
+    let Some(first) = new.first() else {
+        return;
+    };
 
-        get_history(
-            symbol.to_string(),
-            history.clone(),
-            start_datetime,
-            end_datetime,
-        )
-        .await;
+    let first = first.timestamp;
 
-        let history = history.lock().unwrap();
+    history.retain(|quote| quote.timestamp + bar_length <= first);
 
-        if let Some(history) = history.as_ref() {
-            self.history.insert(symbol.to_string(), history.clone());
+    for quote in new {
+        match history.last_mut() {
+            Some(last) if last.timestamp == quote.timestamp => *last = quote,
+            _ => history.push(quote),
         }
     }
 }
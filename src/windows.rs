@@ -1,8 +1,20 @@
+mod alerts;
+mod notifications;
 mod plot;
+mod portfolio;
+mod requests;
+mod scheduler;
 mod search;
+mod watchlist;
 
-pub use plot::PlotWindow;
+pub use alerts::AlertsWindow;
+pub use notifications::NotificationsPanel;
+pub use plot::{ChartState, Drawing, PlotWindow};
+pub use portfolio::PortfolioWindow;
+pub use requests::RequestsWindow;
+pub use scheduler::SchedulerWindow;
 pub use search::SearchWindow;
+pub use watchlist::{WatchlistWindow, Watchlists};
 
 pub trait ViewWindow {
     fn view(&mut self, ui: &mut eframe::egui::Ui);
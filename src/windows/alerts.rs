@@ -0,0 +1,309 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use market_view::{
+    alerts::{AlertBook, Condition},
+    indicators::Indicator,
+    Interval, Storage,
+};
+
+use super::ViewWindow;
+
+/// How often the alerts are read back from the storage.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+const CONDITIONS: [&str; 3] = ["Crosses level", "Moves %", "Indicator cross"];
+
+/// Input fields for a new alert, trendline alerts are added from the charts.
+struct AlertForm {
+    symbol: String,
+    interval: Interval,
+    condition: usize,
+    level: f64,
+    percent: f64,
+    indicator: usize,
+    indicator_level: Option<f64>,
+    once: bool,
+    /// In bars of the interval.
+    cooldown: u64,
+}
+
+impl Default for AlertForm {
+    fn default() -> Self {
+        AlertForm {
+            symbol: String::new(),
+            interval: Interval::OneDay,
+            condition: 0,
+            level: 0.0,
+            percent: 5.0,
+            indicator: 0,
+            indicator_level: None,
+            once: false,
+            cooldown: 0,
+        }
+    }
+}
+
+impl AlertForm {
+    fn condition(&self) -> Condition {
+        match self.condition {
+            0 => Condition::CrossesLevel { level: self.level },
+            1 => Condition::PercentMove {
+                percent: self.percent,
+            },
+            _ => Condition::IndicatorCross {
+                indicator: Indicator::defaults()[self.indicator].clone(),
+                level: self.indicator_level,
+            },
+        }
+    }
+}
+
+pub struct AlertsWindow {
+    storage: Storage,
+    alerts: Arc<AlertBook>,
+    /// Length of the history when the log was last looked at.
+    seen: usize,
+    form: AlertForm,
+    last_snapshot: Option<Instant>,
+}
+
+impl AlertsWindow {
+    pub fn new(storage: Storage) -> Self {
+        let alerts = storage.alerts();
+
+        AlertsWindow {
+            storage,
+            seen: alerts.history.len(),
+            alerts,
+            form: AlertForm::default(),
+            last_snapshot: None,
+        }
+    }
+
+    fn snapshot(&mut self) {
+        self.alerts = self.storage.alerts();
+        self.seen = self.seen.min(self.alerts.history.len());
+        self.last_snapshot = Some(Instant::now());
+    }
+
+    /// Applies a change to the stored alerts and shows it right away.
+    fn modify(&mut self, function: impl FnOnce(&mut AlertBook)) {
+        self.storage.modify_alerts(function);
+
+        self.snapshot();
+    }
+
+    fn form(&mut self, ui: &mut egui::Ui) {
+        let form = &mut self.form;
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut form.symbol)
+                    .hint_text("Symbol")
+                    .desired_width(60.0),
+            );
+
+            egui::ComboBox::from_id_source("Alert interval")
+                .width(50.0)
+                .selected_text(form.interval.as_str())
+                .show_ui(ui, |ui| {
+                    for interval in Interval::ALL {
+                        ui.selectable_value(&mut form.interval, interval, interval.as_str());
+                    }
+                });
+
+            egui::ComboBox::from_id_source("Alert condition").show_index(
+                ui,
+                &mut form.condition,
+                CONDITIONS.len(),
+                |index| CONDITIONS[index],
+            );
+
+            match form.condition {
+                0 => {
+                    ui.add(egui::DragValue::new(&mut form.level).speed(0.1));
+                }
+                1 => {
+                    ui.add(
+                        egui::DragValue::new(&mut form.percent)
+                            .speed(0.1)
+                            .clamp_range(0.0..=100.0)
+                            .suffix("%"),
+                    );
+                }
+                _ => {
+                    let indicators = Indicator::defaults();
+
+                    egui::ComboBox::from_id_source("Alert indicator").show_index(
+                        ui,
+                        &mut form.indicator,
+                        indicators.len(),
+                        |index| indicators[index].label(),
+                    );
+
+                    let mut has_level = form.indicator_level.is_some();
+
+                    if ui.checkbox(&mut has_level, "Level").changed() {
+                        form.indicator_level = has_level.then_some(50.0);
+                    }
+
+                    if let Some(level) = &mut form.indicator_level {
+                        ui.add(egui::DragValue::new(level).speed(0.1));
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut form.once, "Once")
+                .on_hover_text("Disable the alert after it triggered");
+
+            ui.add_enabled(
+                !form.once,
+                egui::DragValue::new(&mut form.cooldown)
+                    .prefix("Cooldown: ")
+                    .suffix(" bars"),
+            );
+        });
+
+        let symbol = self.form.symbol.trim().to_uppercase();
+
+        if ui
+            .add_enabled(!symbol.is_empty(), egui::Button::new("Add alert"))
+            .clicked()
+        {
+            let interval = self.form.interval;
+            let condition = self.form.condition();
+            let once = self.form.once;
+            let cooldown = self.form.cooldown * interval.seconds();
+
+            self.modify(|alerts| {
+                let alert = alerts.add(&symbol, interval, condition);
+
+                alert.once = once;
+                alert.cooldown = cooldown;
+            });
+        }
+    }
+
+    /// Bottom panel listing triggered alerts, must be shown before the central panel.
+    pub fn log_panel(&mut self, ctx: &egui::Context) {
+        let unseen = self.alerts.history.len() - self.seen;
+
+        egui::TopBottomPanel::bottom("Alert log")
+            .resizable(true)
+            .show(ctx, |ui| {
+                let header = if unseen > 0 {
+                    format!("Alert log ({} new)", unseen)
+                } else {
+                    "Alert log".to_string()
+                };
+
+                let response = egui::CollapsingHeader::new(header)
+                    .id_source("Alert log header")
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("Clear").clicked() {
+                                self.modify(AlertBook::clear_history);
+                            }
+                        });
+
+                        egui::ScrollArea::vertical()
+                            .max_height(150.0)
+                            .show(ui, |ui| {
+                                for event in self.alerts.history.iter().rev() {
+                                    let time = chrono::DateTime::from_timestamp(
+                                        event.triggered_at as i64,
+                                        0,
+                                    )
+                                    .unwrap_or_default()
+                                    .format("%Y-%m-%d %H:%M:%S");
+
+                                    ui.label(format!(
+                                        "{} {} {}: {}",
+                                        time, event.symbol, event.interval, event.message
+                                    ));
+                                }
+
+                                if self.alerts.history.is_empty() {
+                                    ui.label("No alerts triggered yet");
+                                }
+                            });
+                    });
+
+                if response.body_returned.is_some() {
+                    self.seen = self.alerts.history.len();
+                }
+            });
+    }
+}
+
+impl ViewWindow for AlertsWindow {
+    fn view(&mut self, ui: &mut eframe::egui::Ui) {
+        if self
+            .last_snapshot
+            .is_none_or(|last_snapshot| last_snapshot.elapsed() >= SNAPSHOT_INTERVAL)
+        {
+            self.snapshot();
+        }
+
+        ui.ctx().request_repaint_after(SNAPSHOT_INTERVAL);
+
+        egui::Window::new("Alerts")
+            .default_open(false)
+            .show(ui.ctx(), |ui| {
+                self.form(ui);
+
+                ui.separator();
+
+                let mut toggled = None;
+                let mut removed = None;
+
+                for alert in &self.alerts.alerts {
+                    ui.horizontal(|ui| {
+                        let mut enabled = alert.enabled;
+
+                        if ui.checkbox(&mut enabled, "").changed() {
+                            toggled = Some((alert.id, enabled));
+                        }
+
+                        let options = match (alert.once, alert.cooldown) {
+                            (true, _) => " once".to_string(),
+                            (false, 0) => String::new(),
+                            (false, cooldown) => format!(
+                                " cooldown {} bars",
+                                cooldown / alert.interval.seconds().max(1)
+                            ),
+                        };
+
+                        ui.label(format!(
+                            "{} {} {}{}",
+                            alert.symbol,
+                            alert.interval,
+                            alert.condition.describe(),
+                            options
+                        ));
+
+                        if ui.small_button("🗑").clicked() {
+                            removed = Some(alert.id);
+                        }
+                    });
+                }
+
+                if self.alerts.alerts.is_empty() {
+                    ui.label("No alerts");
+                }
+
+                if let Some((id, enabled)) = toggled {
+                    self.modify(|alerts| alerts.set_enabled(id, enabled));
+                }
+
+                if let Some(id) = removed {
+                    self.modify(|alerts| alerts.remove(id));
+                }
+            });
+    }
+}
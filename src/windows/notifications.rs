@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use market_view::{
+    error::{DataError, ErrorLog},
+    Storage,
+};
+
+/// How often a shown error banner checks whether a retry succeeded.
+const BANNER_REFRESH: Duration = Duration::from_secs(1);
+
+/// Collapsible list of recent failed loads and searches.
+pub struct NotificationsPanel {
+    errors: ErrorLog,
+    /// Errors already seen with the panel open.
+    seen: usize,
+}
+
+impl NotificationsPanel {
+    pub fn new(storage: &Storage) -> Self {
+        NotificationsPanel {
+            errors: storage.errors().clone(),
+            seen: 0,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let total = self.errors.total();
+        let unseen = total - self.seen;
+
+        egui::TopBottomPanel::bottom("Notifications")
+            .resizable(true)
+            .show(ctx, |ui| {
+                let header = if unseen > 0 {
+                    format!("Notifications ({} new)", unseen)
+                } else {
+                    "Notifications".to_string()
+                };
+
+                let response = egui::CollapsingHeader::new(header)
+                    .id_source("Notifications header")
+                    .show(ui, |ui| {
+                        if ui.button("Clear").clicked() {
+                            self.errors.clear();
+                        }
+
+                        let records = self.errors.records();
+
+                        egui::ScrollArea::vertical()
+                            .max_height(150.0)
+                            .show(ui, |ui| {
+                                for record in records.iter().rev() {
+                                    let time = chrono::DateTime::<chrono::Local>::from(record.time)
+                                        .format("%Y-%m-%d %H:%M:%S");
+
+                                    ui.colored_label(
+                                        egui::Color32::from_rgb(255, 0, 0),
+                                        format!("{} {}: {}", time, record.context, record.error),
+                                    );
+                                }
+
+                                if records.is_empty() {
+                                    ui.label("No errors");
+                                }
+                            });
+                    });
+
+                if response.body_returned.is_some() {
+                    self.seen = total;
+                }
+            });
+    }
+}
+
+/// Red banner with the error and a retry button, returns whether it was clicked.
+///
+/// Repaints while shown, so the banner goes away once a retry loads even if
+/// nothing else changes on screen.
+pub fn error_banner(ui: &mut egui::Ui, context: &str, error: &DataError) -> bool {
+    ui.ctx().request_repaint_after(BANNER_REFRESH);
+
+    egui::Frame::none()
+        .fill(ui.visuals().error_fg_color.gamma_multiply(0.2))
+        .stroke(egui::Stroke::new(1.0, ui.visuals().error_fg_color))
+        .inner_margin(4.0)
+        .rounding(2.0)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("{}: {}", context, error),
+                );
+
+                let retry = ui.button("Retry");
+
+                match error.is_transient() {
+                    true => retry.on_hover_text("The error may be temporary"),
+                    false => retry,
+                }
+                .clicked()
+            })
+            .inner
+        })
+        .inner
+}
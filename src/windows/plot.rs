@@ -1,43 +1,173 @@
-use anyhow::Context;
-use egui::Sense;
-use egui_plot::{
-    BoxElem, BoxPlot, BoxSpread, CoordinatesFormatter, Corner, Line, Plot, PlotPoints,
+mod backtest;
+mod chart;
+mod drawings;
+mod indicators;
+mod volume;
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
 };
+
+use egui_plot::{CoordinatesFormatter, Corner, Plot, PlotBounds, PlotUi};
+use serde::{Deserialize, Serialize};
 use yahoo_finance_api::Quote;
 
-use super::ViewWindow;
+use market_view::{storage::Subscription, Interval, Storage};
+
+use crate::annotations::{self, Annotations};
+
+use super::{notifications::error_banner, ViewWindow};
+
+use backtest::BacktestPanel;
+use chart::ChartType;
+use drawings::DrawingTools;
+use indicators::{IndicatorPanel, IndicatorSettings};
+use volume::VolumePanel;
+
+pub use drawings::Drawing;
 
-struct LineInfo {
-    start: [f64; 2],
-    end: [f64; 2],
-    is_fixed: bool,
+/// Share of the window height taken by each pane below the price chart.
+const PANE_SHARE: f32 = 0.2;
+
+/// Keeps window ids unique when several windows are opened at once.
+static WINDOW_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// The persistent part of a [`PlotWindow`], quotes are fetched again on restore.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChartState {
+    pub symbol: String,
+    pub interval: Interval,
+    pub position: Option<[f32; 2]>,
+    pub size: Option<[f32; 2]>,
+    #[serde(default)]
+    pub chart_type: ChartType,
+    pub volume: VolumePanel,
+    pub indicators: Vec<IndicatorSettings>,
 }
 
 pub struct PlotWindow {
+    storage: Storage,
     symbol: String,
+    interval: Interval,
+    /// Interval selected by the user whose quotes are still being fetched.
+    pending_interval: Option<Interval>,
     quotes: Vec<Quote>,
+    chart_type: ChartType,
+    /// Notifies about new bars of the shown interval.
+    subscription: Option<Subscription>,
+    /// Last bar before new bars arrived, the view follows them if it was visible.
+    previous_last_bar: Option<f64>,
     id: String,
     request_close: bool,
-    line_info: Option<LineInfo>,
-    lines: Vec<LineInfo>,
+    drawings: DrawingTools,
+    annotations: Annotations,
+    /// Revision of the symbol's drawings shown, see [`Annotations::set`].
+    annotations_revision: u64,
+    /// File the drawings are exported to and imported from.
+    drawings_path: String,
+    drawings_message: Option<String>,
+    volume: VolumePanel,
+    indicators: IndicatorPanel,
+    backtest: BacktestPanel,
+    /// Window rectangle of the last frame, or the one to restore.
+    rect: Option<egui::Rect>,
 }
 
 impl PlotWindow {
-    pub fn new(symbol: String, quotes: Vec<Quote>) -> Self {
-        let id = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            .to_string();
+    pub fn new(
+        storage: Storage,
+        annotations: Annotations,
+        symbol: String,
+        interval: Interval,
+        quotes: Vec<Quote>,
+    ) -> Self {
+        let id = format!(
+            "{}-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            WINDOW_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let (annotations_revision, drawings) = annotations.drawings(&symbol);
 
         PlotWindow {
+            storage,
+            drawings_path: annotations::export_path(&symbol).display().to_string(),
+            drawings_message: None,
             symbol,
+            interval,
+            pending_interval: None,
             quotes,
+            chart_type: ChartType::default(),
+            subscription: None,
+            previous_last_bar: None,
             id,
             request_close: false,
-            line_info: None,
-            lines: vec![],
+            drawings: DrawingTools::new(drawings),
+            annotations,
+            annotations_revision,
+            volume: VolumePanel::default(),
+            indicators: IndicatorPanel::default(),
+            backtest: BacktestPanel::default(),
+            rect: None,
+        }
+    }
+
+    /// Reopens a chart, its quotes are loaded from the storage.
+    pub fn from_state(storage: Storage, annotations: Annotations, state: ChartState) -> Self {
+        let mut plot_window = PlotWindow::open(storage, annotations, state.symbol, state.interval);
+
+        plot_window.chart_type = state.chart_type;
+        plot_window.volume = state.volume;
+
+        for settings in state.indicators {
+            plot_window.indicators.add_settings(settings);
+        }
+
+        if let (Some(position), Some(size)) = (state.position, state.size) {
+            plot_window.rect = Some(egui::Rect::from_min_size(position.into(), size.into()));
         }
+
+        plot_window
+    }
+
+    /// A chart that shows its quotes once the storage has them, fetching
+    /// them if needed.
+    pub fn open(
+        storage: Storage,
+        annotations: Annotations,
+        symbol: String,
+        interval: Interval,
+    ) -> Self {
+        let mut plot_window = PlotWindow::new(storage, annotations, symbol, interval, Vec::new());
+
+        plot_window
+            .storage
+            .update_quotes_checked(&plot_window.symbol, interval.source().0);
+
+        plot_window.pending_interval = Some(interval);
+
+        plot_window
+    }
+
+    pub fn state(&self) -> ChartState {
+        ChartState {
+            symbol: self.symbol.clone(),
+            interval: self.pending_interval.unwrap_or(self.interval),
+            position: self.rect.map(|rect| rect.min.into()),
+            size: self.rect.map(|rect| rect.size().into()),
+            chart_type: self.chart_type,
+            volume: self.volume.clone(),
+            indicators: self.indicators.indicators.clone(),
+        }
+    }
+
+    /// Symbol and the interval shown or being loaded.
+    pub fn series(&self) -> (&str, Interval) {
+        (&self.symbol, self.pending_interval.unwrap_or(self.interval))
     }
 
     pub fn id(&self) -> &str {
@@ -48,36 +178,257 @@ impl PlotWindow {
         self.request_close
     }
 
-    fn new_plot_window(&mut self) -> Plot {
-        let plot = Plot::new(format!("{}", &self.symbol))
-            .x_axis_formatter(|gridmark: egui_plot::GridMark, _, _| {
-                let date = chrono::DateTime::from_timestamp(gridmark.value as i64, 0).unwrap();
+    fn select_interval(&mut self, interval: Interval) {
+        if interval == self.interval {
+            self.pending_interval = None;
+            return;
+        }
+
+        let (source, _) = interval.source();
+
+        self.storage.update_quotes_checked(&self.symbol, source);
+
+        self.pending_interval = Some(interval);
+    }
+
+    fn poll_pending_interval(&mut self) {
+        let Some(interval) = self.pending_interval else {
+            return;
+        };
+
+        let Some(quotes) = self.storage.snapshot().series(&self.symbol, interval) else {
+            return;
+        };
+
+        self.quotes = quotes;
+        self.interval = interval;
+        self.pending_interval = None;
+        self.subscription = None;
+        self.backtest.clear();
+    }
+
+    /// Repaints whenever new bars of the shown interval arrive.
+    fn subscribe(&mut self, ctx: &egui::Context) {
+        let (source, _) = self.interval.source();
+        let ctx = ctx.clone();
+
+        self.subscription = Some(
+            self.storage
+                .subscribe(&self.symbol, source, move || ctx.request_repaint()),
+        );
+    }
+
+    fn poll_updates(&mut self) {
+        if self
+            .subscription
+            .as_mut()
+            .is_some_and(Subscription::changed)
+        {
+            self.reload();
+        }
+    }
+
+    /// Takes the shown series from the storage.
+    fn reload(&mut self) {
+        let Some(quotes) = self.storage.snapshot().series(&self.symbol, self.interval) else {
+            return;
+        };
+
+        self.previous_last_bar = self.quotes.last().map(|quote| quote.timestamp as f64);
+        self.quotes = quotes;
+    }
+
+    /// Moves a zoomed view along with new bars if it showed the previous last bar.
+    fn follow_latest_bar(&mut self, ui: &mut PlotUi) {
+        let (Some(previous_last_bar), Some(last_bar)) =
+            (self.previous_last_bar.take(), self.quotes.last())
+        else {
+            return;
+        };
+
+        let bounds = ui.plot_bounds();
+        let shift = last_bar.timestamp as f64 - previous_last_bar;
+
+        if ui.auto_bounds().x
+            || shift <= 0.0
+            || !(bounds.min()[0]..=bounds.max()[0]).contains(&previous_last_bar)
+        {
+            return;
+        }
+
+        ui.set_plot_bounds(PlotBounds::from_min_max(
+            [bounds.min()[0] + shift, bounds.min()[1]],
+            [bounds.max()[0] + shift, bounds.max()[1]],
+        ));
+    }
+
+    /// Shows why the shown or selected interval could not be loaded.
+    fn error_banner(&self, ui: &mut egui::Ui) {
+        let (symbol, interval) = self.series();
+        let snapshot = self.storage.snapshot();
+
+        let Some(error) = snapshot.error(symbol, interval) else {
+            return;
+        };
+
+        if error_banner(ui, &format!("Loading {} {}", symbol, interval), error) {
+            let (source, _) = interval.source();
+
+            self.storage
+                .refresh_quotes(symbol, source, std::time::Duration::ZERO);
+        }
+    }
+
+    /// Shared by all panes of this window so they zoom and pan together.
+    fn link_group(&self) -> egui::Id {
+        egui::Id::new(("plot link", &self.id))
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for interval in Interval::ALL {
+                let selected = self.pending_interval.unwrap_or(self.interval) == interval;
+
+                if ui.selectable_label(selected, interval.as_str()).clicked() {
+                    self.select_interval(interval);
+                }
+            }
+
+            if self.pending_interval.is_some() {
+                ui.spinner();
+            }
+
+            ui.separator();
+
+            self.chart_type.controls(ui, &self.id);
+
+            ui.separator();
+
+            self.volume.controls(ui);
+
+            ui.separator();
+
+            self.indicators.controls(ui);
+
+            ui.separator();
+
+            self.backtest.controls(ui, &self.quotes);
+
+            ui.separator();
+
+            self.alert_controls(ui);
+
+            ui.separator();
+
+            self.drawings_menu(ui);
+        });
+
+        self.drawings.toolbar(ui);
+    }
+
+    fn drawings_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Drawings", |ui| {
+            ui.label(format!(
+                "{} drawing(s) on {}, shown on every timeframe",
+                self.drawings.drawings.len(),
+                self.symbol
+            ));
+
+            ui.horizontal(|ui| {
+                ui.label("File");
+                ui.text_edit_singleline(&mut self.drawings_path);
+            });
+
+            ui.horizontal(|ui| {
+                let path = std::path::PathBuf::from(&self.drawings_path);
+
+                if ui.button("Export").clicked() {
+                    self.drawings_message = Some(
+                        match annotations::export(&path, &self.symbol, &self.drawings.drawings) {
+                            Ok(()) => format!("Exported to {}", path.display()),
+                            Err(e) => format!("{:#}", e),
+                        },
+                    );
+                }
+
+                if ui.button("Import").clicked() {
+                    self.drawings_message = Some(match annotations::import(&path) {
+                        Ok(drawings) => {
+                            let message = format!("Imported {} drawing(s)", drawings.len());
+
+                            self.drawings.extend(drawings);
+
+                            message
+                        }
+                        Err(e) => format!("{:#}", e),
+                    });
+                }
+            });
+
+            if let Some(message) = &self.drawings_message {
+                ui.label(message);
+            }
+        });
+    }
+
+    /// Stores edited drawings for the symbol, or shows the ones edited in
+    /// another chart of it.
+    fn sync_drawings(&mut self) {
+        if self.drawings.take_changed() {
+            self.annotations_revision = self
+                .annotations
+                .set(&self.symbol, self.drawings.drawings.clone());
+        } else if !self.drawings.is_dragging()
+            && self.annotations.revision(&self.symbol) != self.annotations_revision
+        {
+            let (revision, drawings) = self.annotations.drawings(&self.symbol);
+
+            self.drawings.replace(drawings);
+            self.annotations_revision = revision;
+        }
+    }
+
+    fn alert_controls(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Alerts", |ui| {
+            let conditions = self
+                .drawings
+                .drawings
+                .iter()
+                .filter_map(|drawing| drawing.shape.condition())
+                .collect::<Vec<_>>();
+
+            for condition in conditions.iter() {
+                if ui
+                    .button(format!("Alert when the price {}", condition.describe()))
+                    .clicked()
+                {
+                    self.storage.modify_alerts(|alerts| {
+                        alerts.add(&self.symbol, self.interval, condition.clone());
+                    });
+
+                    ui.close_menu();
+                }
+            }
+
+            if conditions.is_empty() {
+                ui.label(
+                    "Draw a trendline, ray or horizontal line to alert when the price crosses it",
+                );
+            }
+        });
+    }
+
+    fn new_plot_window(&mut self, height: Option<f32>) -> Plot {
+        let link_group = self.link_group();
+
+        let mut plot = date_plot(format!("{} price", self.id), self.interval.date_format())
+            .link_axis(link_group, true, false)
+            .link_cursor(link_group, true, false);
+
+        if let Some(height) = height {
+            plot = plot.height(height);
+        }
 
-                date.format("%Y-%m-%d").to_string()
-            })
-            .label_formatter(|_, plot_point| {
-                format!(
-                    "Date: {}\nValue: {}",
-                    chrono::DateTime::from_timestamp(plot_point.x as i64, 0)
-                        .unwrap()
-                        .format("%Y-%m-%d")
-                        .to_string(),
-                    plot_point.y
-                )
-            })
-            .coordinates_formatter(
-                Corner::LeftTop,
-                CoordinatesFormatter::new(|plot_point, _| {
-                    format!(
-                        "Date: {}\nValue: {}",
-                        chrono::DateTime::from_timestamp(plot_point.x as i64, 0)
-                            .unwrap()
-                            .format("%Y-%m-%d")
-                            .to_string(),
-                        plot_point.y
-                    )
-                }),
-            );
         plot
     }
 
@@ -86,167 +437,151 @@ impl PlotWindow {
         plot: Plot,
         ui: &mut egui::Ui,
         transform: &mut Option<egui_plot::PlotTransform>,
+        bars: &Arc<[Quote]>,
     ) -> egui_plot::PlotResponse<()> {
+        let indicator_tooltip = self.indicators.tooltip();
+
         let plot_response = plot.show(ui, |ui| {
             *transform = Some(*ui.transform());
 
-            let boxplot = BoxPlot::new(
-                self.quotes
-                    .iter()
-                    .map(|quote| {
-                        let lower_whisker = quote.low;
-                        let lower_quartile = quote.open.min(quote.close);
-                        let median = (quote.open + quote.close) / 2.0;
-                        let upper_quartile = quote.open.max(quote.close);
-                        let upper_whisker = quote.high;
-
-                        let color = if quote.open < quote.close {
-                            egui::Color32::from_rgb(0, 255, 0)
-                        } else {
-                            egui::Color32::from_rgb(255, 0, 0)
-                        };
-
-                        BoxElem::new(
-                            quote.timestamp as f64,
-                            BoxSpread::new(
-                                lower_whisker,
-                                lower_quartile,
-                                median,
-                                upper_quartile,
-                                upper_whisker,
-                            ),
-                        )
-                        .whisker_width(0.0)
-                        .box_width(40000.0)
-                        .fill(color)
-                        .stroke(egui::Stroke::new(1.0, color))
-                    })
-                    .collect::<Vec<_>>(),
-            )
-            .element_formatter(Box::new(|elem, _| {
-                let is_red = elem.fill == egui::Color32::from_rgb(255, 0, 0);
-
-                let (open, close) = if is_red {
-                    (elem.spread.quartile1, elem.spread.quartile3)
-                } else {
-                    (elem.spread.quartile3, elem.spread.quartile1)
-                };
-
-                let x = elem.argument as i64;
-
-                let x = chrono::DateTime::from_timestamp(x, 0)
-                    .unwrap()
-                    .format("%Y-%m-%d")
-                    .to_string();
+            self.follow_latest_bar(ui);
 
-                let low = elem.spread.lower_whisker;
-                let high = elem.spread.upper_whisker;
+            self.chart_type
+                .show(ui, bars, self.interval, indicator_tooltip);
 
-                format!(
-                    "Date: {}\n\nOpen: {:.2}\nClose: {:.2}\nHigh: {:.2}\nLow: {:.2}",
-                    x, open, close, high, low
-                )
-            }));
+            self.indicators.show_overlays(ui);
 
-            ui.box_plot(boxplot);
+            self.backtest.show_markers(ui);
 
-            for line in self.lines.iter() {
-                ui.line(Line::new(PlotPoints::new(
-                    [line.start, line.end]
-                        .iter()
-                        .map(|pos| [pos[0], pos[1]])
-                        .collect::<Vec<_>>(),
-                )));
-            }
-
-            if let Some(line_info) = &self.line_info {
-                ui.line(Line::new(PlotPoints::new(
-                    [line_info.start, line_info.end]
-                        .iter()
-                        .map(|pos| [pos[0], pos[1]])
-                        .collect::<Vec<_>>(),
-                )))
-            }
+            self.drawings.show(ui, bars);
         });
         plot_response
     }
 }
 
+/// Plot with dates on the x axis and date/value tooltips.
+pub(super) fn date_plot(id: impl std::hash::Hash, date_format: &'static str) -> Plot {
+    Plot::new(id)
+        .x_axis_formatter(move |gridmark: egui_plot::GridMark, _, _| {
+            let date = chrono::DateTime::from_timestamp(gridmark.value as i64, 0).unwrap();
+
+            date.format(date_format).to_string()
+        })
+        .label_formatter(move |_, plot_point| {
+            format!(
+                "Date: {}\nValue: {}",
+                chrono::DateTime::from_timestamp(plot_point.x as i64, 0)
+                    .unwrap()
+                    .format(date_format),
+                plot_point.y
+            )
+        })
+        .coordinates_formatter(
+            Corner::LeftTop,
+            CoordinatesFormatter::new(move |plot_point, _| {
+                format!(
+                    "Date: {}\nValue: {}",
+                    chrono::DateTime::from_timestamp(plot_point.x as i64, 0)
+                        .unwrap()
+                        .format(date_format),
+                    plot_point.y
+                )
+            }),
+        )
+}
+
 impl ViewWindow for PlotWindow {
     fn view(&mut self, ui: &mut eframe::egui::Ui) {
-        egui::Window::new(format!("{}", &self.symbol))
+        let mut window = egui::Window::new(&self.symbol)
             .id(self.id.clone().into())
-            .title_bar(true)
+            .title_bar(true);
+
+        if let Some(rect) = self.rect {
+            window = window.default_pos(rect.min).default_size(rect.size());
+        }
+
+        let response = window
             .show(ui.ctx(), |ui| {
-                let plot = self.new_plot_window();
+                self.poll_pending_interval();
 
-                let mut transform = None;
+                if self.subscription.is_none() {
+                    self.subscribe(ui.ctx());
 
-                let plot_response = self.plot_show(plot, ui, &mut transform);
+                    // Bars stored before subscribing were never notified.
+                    self.reload();
+                }
 
-                let plot_rect = plot_response.response.rect;
+                self.poll_updates();
 
-                let hover_pos = plot_response.response.hover_pos();
+                self.toolbar(ui);
 
-                if plot_response.response.clicked() {
-                    match &mut self.line_info {
-                        None => {
-                            log::debug!("Drag started");
-                            if let Some(screen_pos) = hover_pos {
-                                log::debug!("Clicked at {:?}", screen_pos);
+                self.error_banner(ui);
 
-                                let plot_pos = match transform {
-                                    Some(transform) => transform.value_from_position(screen_pos),
-                                    None => return,
-                                };
+                self.indicators.update(&self.quotes);
 
-                                self.line_info = Some(LineInfo {
-                                    start: [plot_pos.x, plot_pos.y],
-                                    end: [plot_pos.x, plot_pos.y],
-                                    is_fixed: false,
-                                });
-                            }
-                        }
-                        Some(line_info) => {
-                            log::debug!("Drag ended");
-                            if let Some(screen_pos) = hover_pos {
-                                log::debug!("Clicked at {:?}", screen_pos);
+                let pane_count = self.volume.visible as usize
+                    + self.indicators.pane_count()
+                    + self.backtest.pane_count();
 
-                                let plot_pos = match transform {
-                                    Some(transform) => transform.value_from_position(screen_pos),
-                                    None => return,
-                                };
+                let available_height = ui.available_height();
+                let pane_height = available_height * PANE_SHARE;
 
-                                line_info.end = [plot_pos.x, plot_pos.y];
+                let price_height = (pane_count > 0).then(|| {
+                    (available_height - pane_height * pane_count as f32).max(available_height * 0.4)
+                });
 
-                                line_info.is_fixed = true;
-                            }
-                        }
-                    }
+                let plot = self
+                    .new_plot_window(price_height)
+                    .allow_drag(self.drawings.allow_drag(ui));
 
-                    if self.line_info.as_ref().unwrap().is_fixed {
-                        self.lines.push(self.line_info.take().unwrap());
-                    }
-                }
+                // Drawings snap to the bars as drawn, e.g. Heikin-Ashi wicks.
+                let bars = self.chart_type.bars(&self.quotes);
 
-                if let Some(line_info) = &mut self.line_info {
-                    if !line_info.is_fixed {
-                        if let Some(screen_pos) = hover_pos {
-                            let plot_pos = match transform {
-                                Some(transform) => transform.value_from_position(screen_pos),
-                                None => return,
-                            };
+                let mut transform = None;
 
-                            line_info.end = [plot_pos.x, plot_pos.y];
-                        }
-                    }
+                let plot_response = self.plot_show(plot, ui, &mut transform, &bars);
+
+                if let Some(transform) = transform {
+                    self.drawings
+                        .interact(&plot_response.response, transform, &bars);
                 }
+
+                self.sync_drawings();
+
+                if self.volume.visible {
+                    self.volume.show(
+                        ui,
+                        &self.id,
+                        self.link_group(),
+                        &self.quotes,
+                        self.interval,
+                        pane_height,
+                    );
+                }
+
+                self.indicators.show_panes(
+                    ui,
+                    &self.id,
+                    self.link_group(),
+                    self.interval,
+                    pane_height,
+                );
+
+                self.backtest.show_pane(
+                    ui,
+                    &self.id,
+                    self.link_group(),
+                    self.interval,
+                    pane_height,
+                );
             })
             .unwrap()
-            .response
-            .double_clicked()
-            .then(|| {
-                self.request_close = true;
-            });
+            .response;
+
+        self.rect = Some(response.rect);
+
+        response.double_clicked().then(|| {
+            self.request_close = true;
+        });
     }
 }
@@ -0,0 +1,285 @@
+use egui::Color32;
+use egui_plot::{Line, MarkerShape, PlotUi, Points};
+use yahoo_finance_api::Quote;
+
+use market_view::{
+    backtest::{
+        self, BacktestConfig, BacktestReport, Fill, Order, RsiReversion, Slippage, SmaCrossover,
+    },
+    interval::Interval,
+};
+
+use super::date_plot;
+
+#[derive(Clone, Copy, PartialEq)]
+enum StrategyChoice {
+    SmaCrossover {
+        fast: usize,
+        slow: usize,
+    },
+    RsiReversion {
+        period: usize,
+        oversold: f64,
+        overbought: f64,
+    },
+}
+
+impl StrategyChoice {
+    const DEFAULTS: [StrategyChoice; 2] = [
+        StrategyChoice::SmaCrossover { fast: 20, slow: 50 },
+        StrategyChoice::RsiReversion {
+            period: 14,
+            oversold: 30.0,
+            overbought: 70.0,
+        },
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            StrategyChoice::SmaCrossover { .. } => "SMA crossover",
+            StrategyChoice::RsiReversion { .. } => "RSI reversion",
+        }
+    }
+
+    fn build(&self) -> Box<dyn backtest::Strategy> {
+        match *self {
+            StrategyChoice::SmaCrossover { fast, slow } => Box::new(SmaCrossover::new(fast, slow)),
+            StrategyChoice::RsiReversion {
+                period,
+                oversold,
+                overbought,
+            } => Box::new(RsiReversion::new(period, oversold, overbought)),
+        }
+    }
+}
+
+/// Strategy settings of a window and the markers of its last run.
+pub struct BacktestPanel {
+    strategy: StrategyChoice,
+    config: BacktestConfig,
+    report: Option<BacktestReport>,
+    show_equity: bool,
+}
+
+impl Default for BacktestPanel {
+    fn default() -> Self {
+        BacktestPanel {
+            strategy: StrategyChoice::DEFAULTS[0],
+            config: BacktestConfig::default(),
+            report: None,
+            show_equity: true,
+        }
+    }
+}
+
+impl BacktestPanel {
+    pub fn controls(&mut self, ui: &mut egui::Ui, quotes: &[Quote]) {
+        ui.menu_button("Backtest", |ui| {
+            ui.horizontal(|ui| {
+                for strategy in StrategyChoice::DEFAULTS {
+                    let selected = strategy.name() == self.strategy.name();
+
+                    if ui.selectable_label(selected, strategy.name()).clicked() && !selected {
+                        self.strategy = strategy;
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| match &mut self.strategy {
+                StrategyChoice::SmaCrossover { fast, slow } => {
+                    ui.label("Fast");
+                    ui.add(egui::DragValue::new(fast).clamp_range(1..=500));
+                    ui.label("Slow");
+                    ui.add(egui::DragValue::new(slow).clamp_range(1..=500));
+                }
+                StrategyChoice::RsiReversion {
+                    period,
+                    oversold,
+                    overbought,
+                } => {
+                    ui.label("Period");
+                    ui.add(egui::DragValue::new(period).clamp_range(1..=500));
+                    ui.label("Buy below");
+                    ui.add(egui::DragValue::new(oversold).clamp_range(0.0..=100.0));
+                    ui.label("Sell above");
+                    ui.add(egui::DragValue::new(overbought).clamp_range(0.0..=100.0));
+                }
+            });
+
+            ui.separator();
+
+            let config = &mut self.config;
+
+            ui.horizontal(|ui| {
+                ui.label("Initial cash");
+                ui.add(
+                    egui::DragValue::new(&mut config.initial_cash)
+                        .speed(100.0)
+                        .clamp_range(1.0..=f64::MAX),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Commission per order");
+                ui.add(
+                    egui::DragValue::new(&mut config.commission.per_order)
+                        .speed(0.1)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+                ui.label("+ %");
+                percent_value(ui, &mut config.commission.rate);
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Slippage");
+
+                let relative = matches!(config.slippage, Slippage::Relative(_));
+
+                if ui.selectable_label(relative, "%").clicked() && !relative {
+                    config.slippage = Slippage::Relative(0.0);
+                }
+
+                if ui.selectable_label(!relative, "Per share").clicked() && relative {
+                    config.slippage = Slippage::Fixed(0.0);
+                }
+
+                match &mut config.slippage {
+                    Slippage::Relative(fraction) => percent_value(ui, fraction),
+                    Slippage::Fixed(amount) => {
+                        ui.add(
+                            egui::DragValue::new(amount)
+                                .speed(0.01)
+                                .clamp_range(0.0..=f64::MAX),
+                        );
+                    }
+                }
+            });
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Run").clicked() {
+                    let mut strategy = self.strategy.build();
+
+                    self.report = Some(backtest::run(quotes, strategy.as_mut(), &self.config));
+                }
+
+                if ui
+                    .add_enabled(self.report.is_some(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    self.clear();
+                }
+
+                ui.checkbox(&mut self.show_equity, "Equity pane");
+            });
+
+            if let Some(report) = &self.report {
+                let statistics = &report.statistics;
+
+                ui.label(format!(
+                    "Return: {:.2}%\nCAGR: {:.2}%\nSharpe: {:.2}\nMax drawdown: {:.2}%\nTrades: {}\nWin rate: {:.1}%",
+                    statistics.total_return * 100.0,
+                    statistics.cagr * 100.0,
+                    statistics.sharpe,
+                    statistics.max_drawdown * 100.0,
+                    report.trades.len(),
+                    statistics.win_rate * 100.0,
+                ));
+            }
+        });
+    }
+
+    /// Drops the results, e.g. once they no longer match the shown quotes.
+    pub fn clear(&mut self) {
+        self.report = None;
+    }
+
+    pub fn pane_count(&self) -> usize {
+        (self.show_equity && self.report.is_some()) as usize
+    }
+
+    /// Buy and sell markers at the fill prices.
+    pub fn show_markers(&self, ui: &mut PlotUi) {
+        let Some(report) = &self.report else {
+            return;
+        };
+
+        let (buys, sells): (Vec<&Fill>, Vec<&Fill>) = report
+            .fills
+            .iter()
+            .partition(|fill| matches!(fill.order, Order::Buy(_)));
+
+        for (fills, name, shape, color) in [
+            (buys, "Buy", MarkerShape::Up, Color32::from_rgb(0, 200, 255)),
+            (
+                sells,
+                "Sell",
+                MarkerShape::Down,
+                Color32::from_rgb(255, 160, 0),
+            ),
+        ] {
+            ui.points(
+                Points::new(
+                    fills
+                        .iter()
+                        .map(|fill| [fill.timestamp as f64, fill.price])
+                        .collect::<Vec<_>>(),
+                )
+                .shape(shape)
+                .radius(6.0)
+                .filled(true)
+                .color(color)
+                .name(name),
+            );
+        }
+    }
+
+    /// Equity curve with its x axis linked to `link_group`.
+    pub fn show_pane(
+        &self,
+        ui: &mut egui::Ui,
+        id: &str,
+        link_group: egui::Id,
+        interval: Interval,
+        height: f32,
+    ) {
+        let Some(report) = self.report.as_ref().filter(|_| self.show_equity) else {
+            return;
+        };
+
+        let date_format = interval.date_format();
+
+        date_plot(format!("{} equity", id), date_format)
+            .height(height)
+            .link_axis(link_group, true, false)
+            .link_cursor(link_group, true, false)
+            .allow_zoom([true, false])
+            .allow_drag([true, false])
+            .auto_bounds([false, true].into())
+            .y_axis_label("Equity")
+            .show(ui, |ui| {
+                ui.line(
+                    Line::new(report.equity.clone())
+                        .color(Color32::LIGHT_BLUE)
+                        .name("Equity"),
+                );
+            });
+    }
+}
+
+fn percent_value(ui: &mut egui::Ui, fraction: &mut f64) {
+    let mut percent = *fraction * 100.0;
+
+    if ui
+        .add(
+            egui::DragValue::new(&mut percent)
+                .speed(0.01)
+                .clamp_range(0.0..=100.0)
+                .suffix("%"),
+        )
+        .changed()
+    {
+        *fraction = percent / 100.0;
+    }
+}
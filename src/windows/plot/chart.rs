@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use egui::Color32;
+use egui_plot::{BoxElem, BoxPlot, BoxSpread, Line, PlotPoints, PlotUi};
+use serde::{Deserialize, Serialize};
+use yahoo_finance_api::Quote;
+
+use market_view::{resample::heikin_ashi, Interval};
+
+const UP_COLOR: Color32 = Color32::from_rgb(0, 255, 0);
+
+const DOWN_COLOR: Color32 = Color32::from_rgb(255, 0, 0);
+
+const LINE_COLOR: Color32 = Color32::from_rgb(0, 170, 255);
+
+/// How the bars of a chart are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChartType {
+    #[default]
+    Candles,
+    /// Hollow when the close is above the open, colored by the change from
+    /// the previous close.
+    HollowCandles,
+    HeikinAshi,
+    OhlcBars,
+    Line,
+    Area,
+}
+
+impl ChartType {
+    const ALL: [ChartType; 6] = [
+        ChartType::Candles,
+        ChartType::HollowCandles,
+        ChartType::HeikinAshi,
+        ChartType::OhlcBars,
+        ChartType::Line,
+        ChartType::Area,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ChartType::Candles => "Candles",
+            ChartType::HollowCandles => "Hollow candles",
+            ChartType::HeikinAshi => "Heikin-Ashi",
+            ChartType::OhlcBars => "OHLC bars",
+            ChartType::Line => "Line",
+            ChartType::Area => "Area",
+        }
+    }
+
+    pub fn controls(&mut self, ui: &mut egui::Ui, id: &str) {
+        egui::ComboBox::from_id_source(("Chart type", id))
+            .selected_text(self.label())
+            .show_ui(ui, |ui| {
+                for chart_type in ChartType::ALL {
+                    ui.selectable_value(self, chart_type, chart_type.label());
+                }
+            });
+    }
+
+    /// The bars as drawn, Heikin-Ashi bars replace the quotes.
+    pub fn bars(self, quotes: &[Quote]) -> Arc<[Quote]> {
+        match self {
+            ChartType::HeikinAshi => heikin_ashi(quotes).into(),
+            _ => quotes.into(),
+        }
+    }
+
+    /// Adds `bars`, as returned by [`ChartType::bars`], to `ui`. Hovering a
+    /// bar shows its values and the text `indicator_tooltip` returns for its
+    /// timestamp, whatever the chart type.
+    pub fn show(
+        self,
+        ui: &mut PlotUi,
+        bars: &Arc<[Quote]>,
+        interval: Interval,
+        indicator_tooltip: impl Fn(u64) -> String + 'static,
+    ) {
+        let quotes = &bars[..];
+        let date_format = interval.date_format();
+        let box_width = interval.seconds() as f64 * 0.5;
+
+        let elems = match self {
+            ChartType::Candles | ChartType::HeikinAshi => candles(quotes, box_width, false),
+            ChartType::HollowCandles => candles(quotes, box_width, true),
+            // Invisible, they only carry the tooltips and the bounds.
+            ChartType::OhlcBars | ChartType::Line | ChartType::Area => {
+                candles(quotes, box_width, false)
+                    .into_iter()
+                    .map(|elem| elem.fill(Color32::TRANSPARENT).stroke(egui::Stroke::NONE))
+                    .collect()
+            }
+        };
+
+        let tooltip_quotes = bars.clone();
+
+        ui.box_plot(
+            BoxPlot::new(elems).element_formatter(Box::new(move |elem, _| {
+                let timestamp = elem.argument as u64;
+
+                let Ok(index) =
+                    tooltip_quotes.binary_search_by_key(&timestamp, |quote| quote.timestamp)
+                else {
+                    return String::new();
+                };
+
+                let quote = &tooltip_quotes[index];
+
+                format!(
+                    "Date: {}\n\nOpen: {:.2}\nClose: {:.2}\nHigh: {:.2}\nLow: {:.2}{}",
+                    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+                        .unwrap()
+                        .format(date_format),
+                    quote.open,
+                    quote.close,
+                    quote.high,
+                    quote.low,
+                    indicator_tooltip(timestamp)
+                )
+            })),
+        );
+
+        let closes = || {
+            PlotPoints::new(
+                quotes
+                    .iter()
+                    .map(|quote| [quote.timestamp as f64, quote.close])
+                    .collect(),
+            )
+        };
+
+        match self {
+            ChartType::OhlcBars => ohlc_bars(ui, quotes, box_width / 2.0),
+            ChartType::Line => ui.line(Line::new(closes()).color(LINE_COLOR).width(1.5)),
+            ChartType::Area => {
+                let bottom = quotes
+                    .iter()
+                    .map(|quote| quote.low)
+                    .fold(f64::NAN, f64::min);
+
+                ui.line(
+                    Line::new(closes())
+                        .color(LINE_COLOR)
+                        .width(1.5)
+                        .fill(bottom as f32),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+fn candles(quotes: &[Quote], width: f64, hollow: bool) -> Vec<BoxElem> {
+    let mut previous_close = None;
+
+    quotes
+        .iter()
+        .map(|quote| {
+            let bottom = quote.open.min(quote.close);
+            let top = quote.open.max(quote.close);
+
+            let rising = match hollow {
+                true => quote.close >= previous_close.unwrap_or(quote.open),
+                false => quote.open < quote.close,
+            };
+            let color = if rising { UP_COLOR } else { DOWN_COLOR };
+            let fill = match hollow && quote.close > quote.open {
+                true => Color32::TRANSPARENT,
+                false => color,
+            };
+
+            previous_close = Some(quote.close);
+
+            // The median sits on the top edge, hollow bodies stay empty.
+            BoxElem::new(
+                quote.timestamp as f64,
+                BoxSpread::new(quote.low, bottom, top, top, quote.high),
+            )
+            .whisker_width(0.0)
+            .box_width(width)
+            .fill(fill)
+            .stroke(egui::Stroke::new(1.0, color))
+        })
+        .collect()
+}
+
+/// A vertical line from the low to the high per bar, with the open ticked
+/// on the left and the close on the right. Only the bars in view are added.
+fn ohlc_bars(ui: &mut PlotUi, quotes: &[Quote], tick: f64) {
+    let bounds = ui.plot_bounds();
+    let (left, right) = (bounds.min()[0] - tick, bounds.max()[0] + tick);
+
+    for quote in quotes {
+        let time = quote.timestamp as f64;
+
+        if time < left || time > right {
+            continue;
+        }
+
+        let color = if quote.open < quote.close {
+            UP_COLOR
+        } else {
+            DOWN_COLOR
+        };
+
+        ui.line(
+            Line::new(PlotPoints::new(vec![
+                [time - tick, quote.open],
+                [time, quote.open],
+                [time, quote.high],
+                [time, quote.low],
+                [time, quote.close],
+                [time + tick, quote.close],
+            ]))
+            .color(color),
+        );
+    }
+}
@@ -0,0 +1,1434 @@
+use egui::{Color32, Key, Modifiers, Pos2};
+use egui_plot::{
+    Arrows, HLine, Line, LineStyle, MarkerShape, PlotBounds, PlotPoint, PlotPoints, PlotTransform,
+    PlotUi, Points, Polygon, Text, VLine,
+};
+use serde::{Deserialize, Serialize};
+use yahoo_finance_api::Quote;
+
+use market_view::alerts::Condition;
+
+/// A position in plot coordinates, timestamp and price.
+pub type Point = [f64; 2];
+
+const FIB_RETRACEMENT_LEVELS: [f64; 7] = [0.0, 0.236, 0.382, 0.5, 0.618, 0.786, 1.0];
+
+const FIB_EXTENSION_LEVELS: [f64; 7] = [0.0, 0.618, 1.0, 1.272, 1.618, 2.0, 2.618];
+
+const DEFAULT_COLOR: Color32 = Color32::from_rgb(0, 170, 255);
+
+const PALETTE: [Color32; 6] = [
+    DEFAULT_COLOR,
+    Color32::from_rgb(255, 200, 0),
+    Color32::from_rgb(255, 0, 0),
+    Color32::from_rgb(0, 255, 0),
+    Color32::from_rgb(220, 80, 255),
+    Color32::from_rgb(240, 240, 240),
+];
+
+const WIDTHS: [f32; 4] = [1.0, 1.5, 2.5, 4.0];
+
+/// Pixels between the pointer and a drawing that still count as a hit.
+const HIT_DISTANCE: f32 = 6.0;
+
+/// Pixels between the pointer and a handle of the selected drawing that
+/// grab the handle instead of the whole drawing.
+const HANDLE_DISTANCE: f32 = 8.0;
+
+/// Pixels between a point and the open, high, low or close of the nearest
+/// bar that snap it to that price in magnet mode.
+const MAGNET_DISTANCE: f32 = 12.0;
+
+/// Edits that can be undone.
+const UNDO_LENGTH: usize = 100;
+
+/// Geometry of a drawing, all points in plot coordinates.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Shape {
+    TrendLine {
+        start: Point,
+        end: Point,
+    },
+    HorizontalLine {
+        price: f64,
+    },
+    VerticalLine {
+        time: f64,
+    },
+    /// Starts at `start` and extends through `through` to the edge of the plot.
+    Ray {
+        start: Point,
+        through: Point,
+    },
+    /// A trendline and a parallel one `offset` above it.
+    Channel {
+        start: Point,
+        end: Point,
+        offset: f64,
+    },
+    /// Levels between the swing from `start` to `end`, measured back from `end`.
+    FibRetracement {
+        start: Point,
+        end: Point,
+    },
+    /// Levels of the swing from `start` to `end` projected from `projection`.
+    FibExtension {
+        start: Point,
+        end: Point,
+        projection: Point,
+    },
+    Rectangle {
+        corner: Point,
+        opposite: Point,
+    },
+    Text {
+        position: Point,
+        text: String,
+    },
+    Arrow {
+        start: Point,
+        end: Point,
+    },
+}
+
+impl Shape {
+    /// The alert that fires when the close crosses the drawing, if it is a line.
+    pub fn condition(&self) -> Option<Condition> {
+        match self {
+            Shape::TrendLine { start, end } => Some(Condition::CrossesTrendline {
+                start: *start,
+                end: *end,
+            }),
+            Shape::Ray { start, through } => Some(Condition::CrossesTrendline {
+                start: *start,
+                end: *through,
+            }),
+            Shape::HorizontalLine { price } => Some(Condition::CrossesLevel { level: *price }),
+            _ => None,
+        }
+    }
+
+    /// Points that can be dragged to reshape the drawing.
+    fn handles(&self) -> Vec<Point> {
+        match self {
+            Shape::TrendLine { start, end }
+            | Shape::FibRetracement { start, end }
+            | Shape::Arrow { start, end } => vec![*start, *end],
+            Shape::Ray { start, through } => vec![*start, *through],
+            Shape::Channel { start, end, offset } => vec![
+                *start,
+                *end,
+                [
+                    (start[0] + end[0]) / 2.0,
+                    (start[1] + end[1]) / 2.0 + offset,
+                ],
+            ],
+            Shape::FibExtension {
+                start,
+                end,
+                projection,
+            } => vec![*start, *end, *projection],
+            Shape::Rectangle { corner, opposite } => vec![*corner, *opposite],
+            Shape::Text { position, .. } => vec![*position],
+            Shape::HorizontalLine { .. } | Shape::VerticalLine { .. } => Vec::new(),
+        }
+    }
+
+    fn move_handle(&mut self, handle: usize, point: Point) {
+        match (self, handle) {
+            (
+                Shape::TrendLine { start, .. }
+                | Shape::FibRetracement { start, .. }
+                | Shape::Arrow { start, .. }
+                | Shape::Ray { start, .. }
+                | Shape::Channel { start, .. }
+                | Shape::FibExtension { start, .. },
+                0,
+            ) => *start = point,
+            (
+                Shape::TrendLine { end, .. }
+                | Shape::FibRetracement { end, .. }
+                | Shape::Arrow { end, .. }
+                | Shape::Channel { end, .. }
+                | Shape::FibExtension { end, .. },
+                1,
+            ) => *end = point,
+            (Shape::Ray { through, .. }, 1) => *through = point,
+            (Shape::Channel { start, end, offset }, 2) => {
+                *offset = point[1] - line_at(*start, *end, point[0]);
+            }
+            (Shape::FibExtension { projection, .. }, 2) => *projection = point,
+            (Shape::Rectangle { corner, .. }, 0) => *corner = point,
+            (Shape::Rectangle { opposite, .. }, 1) => *opposite = point,
+            (Shape::Text { position, .. }, 0) => *position = point,
+            _ => {}
+        }
+    }
+
+    fn translate(&mut self, delta: Point) {
+        let shift = |point: &mut Point| {
+            point[0] += delta[0];
+            point[1] += delta[1];
+        };
+
+        match self {
+            Shape::TrendLine { start, end }
+            | Shape::FibRetracement { start, end }
+            | Shape::Arrow { start, end }
+            | Shape::Channel { start, end, .. } => {
+                shift(start);
+                shift(end);
+            }
+            Shape::Ray { start, through } => {
+                shift(start);
+                shift(through);
+            }
+            Shape::FibExtension {
+                start,
+                end,
+                projection,
+            } => {
+                shift(start);
+                shift(end);
+                shift(projection);
+            }
+            Shape::Rectangle { corner, opposite } => {
+                shift(corner);
+                shift(opposite);
+            }
+            Shape::Text { position, .. } => shift(position),
+            Shape::HorizontalLine { price } => *price += delta[1],
+            Shape::VerticalLine { time } => *time += delta[0],
+        }
+    }
+
+    /// The lines of the drawing as far as they reach into `bounds`.
+    fn segments(&self, bounds: &PlotBounds) -> Vec<[Point; 2]> {
+        let levels = |left: f64, right: f64, prices: Vec<f64>| {
+            prices
+                .into_iter()
+                .map(|price| [[left, price], [right, price]])
+                .collect()
+        };
+
+        match self {
+            Shape::TrendLine { start, end } | Shape::Arrow { start, end } => vec![[*start, *end]],
+            Shape::HorizontalLine { price } => {
+                vec![[[bounds.min()[0], *price], [bounds.max()[0], *price]]]
+            }
+            Shape::VerticalLine { time } => {
+                vec![[[*time, bounds.min()[1]], [*time, bounds.max()[1]]]]
+            }
+            Shape::Ray { start, through } => vec![[*start, ray_end(*start, *through, bounds)]],
+            Shape::Channel { start, end, offset } => vec![
+                [*start, *end],
+                [[start[0], start[1] + offset], [end[0], end[1] + offset]],
+            ],
+            Shape::FibRetracement { start, end } => levels(
+                start[0].min(end[0]),
+                start[0].max(end[0]),
+                FIB_RETRACEMENT_LEVELS
+                    .iter()
+                    .map(|level| end[1] - (end[1] - start[1]) * level)
+                    .collect(),
+            ),
+            Shape::FibExtension {
+                start,
+                end,
+                projection,
+            } => levels(
+                projection[0],
+                projection[0] + (end[0] - start[0]).abs(),
+                FIB_EXTENSION_LEVELS
+                    .iter()
+                    .map(|level| projection[1] + (end[1] - start[1]) * level)
+                    .collect(),
+            ),
+            Shape::Rectangle { corner, opposite } => {
+                let corners = [
+                    *corner,
+                    [opposite[0], corner[1]],
+                    *opposite,
+                    [corner[0], opposite[1]],
+                ];
+
+                (0..4)
+                    .map(|index| [corners[index], corners[(index + 1) % 4]])
+                    .collect()
+            }
+            Shape::Text { .. } => Vec::new(),
+        }
+    }
+
+    /// Distance in pixels from the screen position `pos` to the drawing.
+    fn distance(&self, pos: Pos2, transform: &PlotTransform) -> f32 {
+        let screen =
+            |point: &Point| transform.position_from_point(&PlotPoint::new(point[0], point[1]));
+
+        match self {
+            Shape::Text { position, text } => {
+                // Roughly the label, which is anchored at its bottom left.
+                let anchor = screen(position);
+                let rect = egui::Rect::from_min_max(
+                    anchor - egui::vec2(0.0, 14.0),
+                    anchor + egui::vec2(7.0 * text.chars().count() as f32, 0.0),
+                );
+
+                return rect.distance_to_pos(pos);
+            }
+            Shape::Rectangle { corner, opposite }
+                if egui::Rect::from_two_pos(screen(corner), screen(opposite)).contains(pos) =>
+            {
+                return 0.0;
+            }
+            _ => {}
+        }
+
+        self.segments(transform.bounds())
+            .iter()
+            .map(|[start, end]| segment_distance(pos, screen(start), screen(end)))
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+/// Price of the line through `start` and `end` at `x`.
+fn line_at(start: Point, end: Point, x: f64) -> f64 {
+    let run = end[0] - start[0];
+
+    match run == 0.0 {
+        true => start[1],
+        false => start[1] + (end[1] - start[1]) * (x - start[0]) / run,
+    }
+}
+
+fn segment_distance(pos: Pos2, start: Pos2, end: Pos2) -> f32 {
+    let direction = end - start;
+    let length = direction.length_sq();
+
+    let t = match length > 0.0 {
+        true => ((pos - start).dot(direction) / length).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+
+    pos.distance(start + direction * t)
+}
+
+/// `point` moved onto the open, high, low or close of the nearest bar that is
+/// within [`MAGNET_DISTANCE`] pixels of it, otherwise left alone.
+fn snap(point: Point, transform: &PlotTransform, quotes: &[Quote]) -> Point {
+    let after = quotes.partition_point(|quote| (quote.timestamp as f64) < point[0]);
+    let offset = |quote: &&Quote| (quote.timestamp as f64 - point[0]).abs();
+
+    let Some(quote) = [after.checked_sub(1), Some(after)]
+        .into_iter()
+        .flatten()
+        .filter_map(|index| quotes.get(index))
+        .min_by(|a, b| offset(a).total_cmp(&offset(b)))
+    else {
+        return point;
+    };
+
+    let time = quote.timestamp as f64;
+    let y = |price: f64| {
+        transform
+            .position_from_point(&PlotPoint::new(time, price))
+            .y
+    };
+    let distance = |price: f64| (y(price) - y(point[1])).abs();
+
+    [quote.open, quote.high, quote.low, quote.close]
+        .into_iter()
+        .filter(|price| distance(*price) <= MAGNET_DISTANCE)
+        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+        .map_or(point, |price| [time, price])
+}
+
+/// Where a ray from `start` through `through` leaves `bounds`, or `through`
+/// if that lies outside of them.
+fn ray_end(start: Point, through: Point, bounds: &PlotBounds) -> Point {
+    let run = through[0] - start[0];
+
+    if run == 0.0 {
+        let y = match through[1] >= start[1] {
+            true => bounds.max()[1].max(through[1]),
+            false => bounds.min()[1].min(through[1]),
+        };
+
+        return [start[0], y];
+    }
+
+    let x = match run > 0.0 {
+        true => bounds.max()[0].max(through[0]),
+        false => bounds.min()[0].min(through[0]),
+    };
+
+    [x, line_at(start, through, x)]
+}
+
+/// The part of the plot rays extend to. While the plot fits its bounds to
+/// the items that is the range of the bars, otherwise rays would push the
+/// bounds outwards every frame.
+fn ray_bounds(ui: &PlotUi, bars: (f64, f64)) -> PlotBounds {
+    let bounds = ui.plot_bounds();
+    let auto_bounds = ui.auto_bounds();
+
+    let (left, right) = match auto_bounds.x {
+        true => bars,
+        false => (bounds.min()[0], bounds.max()[0]),
+    };
+
+    let (bottom, top) = match auto_bounds.y {
+        true => (f64::INFINITY, f64::NEG_INFINITY),
+        false => (bounds.min()[1], bounds.max()[1]),
+    };
+
+    PlotBounds::from_min_max([left, bottom], [right, top])
+}
+
+/// How the lines of a drawing are stroked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Dash {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl Dash {
+    const ALL: [Dash; 3] = [Dash::Solid, Dash::Dashed, Dash::Dotted];
+
+    fn line_style(self) -> LineStyle {
+        match self {
+            Dash::Solid => LineStyle::Solid,
+            Dash::Dashed => LineStyle::dashed_loose(),
+            Dash::Dotted => LineStyle::dotted_dense(),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Dash::Solid => "Solid",
+            Dash::Dashed => "Dashed",
+            Dash::Dotted => "Dotted",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Drawing {
+    pub shape: Shape,
+    pub color: Color32,
+    pub width: f32,
+    #[serde(default)]
+    pub dash: Dash,
+}
+
+impl Drawing {
+    pub fn new(shape: Shape) -> Self {
+        Drawing {
+            shape,
+            color: DEFAULT_COLOR,
+            width: 1.5,
+            dash: Dash::Solid,
+        }
+    }
+
+    /// Adds the plot items of the drawing to `ui`, `bars` is the time range
+    /// of the chart's bars.
+    fn show(&self, ui: &mut PlotUi, bars: (f64, f64)) {
+        let color = self.color;
+        let width = self.width;
+        let style = self.dash.line_style();
+
+        let line = |points: Vec<Point>| {
+            Line::new(PlotPoints::new(points))
+                .color(color)
+                .width(width)
+                .style(style)
+        };
+        let guide = |points: Vec<Point>| {
+            Line::new(PlotPoints::new(points))
+                .color(color.gamma_multiply(0.5))
+                .style(LineStyle::dashed_dense())
+        };
+        let label = |position: Point, text: String| {
+            Text::new(PlotPoint::new(position[0], position[1]), text)
+                .color(color)
+                .anchor(egui::Align2::LEFT_BOTTOM)
+        };
+
+        match &self.shape {
+            Shape::TrendLine { start, end } => ui.line(line(vec![*start, *end])),
+            Shape::HorizontalLine { price } => {
+                ui.hline(HLine::new(*price).color(color).width(width).style(style))
+            }
+            Shape::VerticalLine { time } => {
+                ui.vline(VLine::new(*time).color(color).width(width).style(style))
+            }
+            Shape::Ray { start, through } => {
+                let end = ray_end(*start, *through, &ray_bounds(ui, bars));
+
+                ui.line(line(vec![*start, end]));
+            }
+            Shape::Channel { start, end, offset } => {
+                let upper = |point: &Point| [point[0], point[1] + offset];
+
+                ui.polygon(
+                    Polygon::new(PlotPoints::new(vec![
+                        *start,
+                        *end,
+                        upper(end),
+                        upper(start),
+                    ]))
+                    .fill_color(color.gamma_multiply(0.1))
+                    .stroke(egui::Stroke::NONE),
+                );
+                ui.line(line(vec![*start, *end]));
+                ui.line(line(vec![upper(start), upper(end)]));
+            }
+            Shape::FibRetracement { start, end } => {
+                let (left, right) = (start[0].min(end[0]), start[0].max(end[0]));
+
+                ui.line(guide(vec![*start, *end]));
+
+                for level in FIB_RETRACEMENT_LEVELS {
+                    let price = end[1] - (end[1] - start[1]) * level;
+
+                    ui.line(line(vec![[left, price], [right, price]]));
+                    ui.text(label(
+                        [left, price],
+                        format!("{:.1}% ({:.2})", level * 100.0, price),
+                    ));
+                }
+            }
+            Shape::FibExtension {
+                start,
+                end,
+                projection,
+            } => {
+                let left = projection[0];
+                let right = left + (end[0] - start[0]).abs();
+
+                ui.line(guide(vec![*start, *end, *projection]));
+
+                for level in FIB_EXTENSION_LEVELS {
+                    let price = projection[1] + (end[1] - start[1]) * level;
+
+                    ui.line(line(vec![[left, price], [right, price]]));
+                    ui.text(label(
+                        [left, price],
+                        format!("{:.1}% ({:.2})", level * 100.0, price),
+                    ));
+                }
+            }
+            Shape::Rectangle { corner, opposite } => ui.polygon(
+                Polygon::new(PlotPoints::new(vec![
+                    *corner,
+                    [opposite[0], corner[1]],
+                    *opposite,
+                    [corner[0], opposite[1]],
+                ]))
+                .fill_color(color.gamma_multiply(0.15))
+                .stroke(egui::Stroke::new(width, color))
+                .style(style),
+            ),
+            Shape::Text { position, text } => ui.text(label(*position, text.clone())),
+            Shape::Arrow { start, end } => ui.arrows(
+                Arrows::new(PlotPoints::new(vec![*start]), PlotPoints::new(vec![*end]))
+                    .color(color),
+            ),
+        }
+    }
+}
+
+/// What a click on the chart does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tool {
+    /// Clicks select drawings, drags move them or pan the chart.
+    #[default]
+    Cursor,
+    TrendLine,
+    HorizontalLine,
+    VerticalLine,
+    Ray,
+    Channel,
+    FibRetracement,
+    FibExtension,
+    Rectangle,
+    Text,
+    Arrow,
+}
+
+impl Tool {
+    const ALL: [Tool; 11] = [
+        Tool::Cursor,
+        Tool::TrendLine,
+        Tool::HorizontalLine,
+        Tool::VerticalLine,
+        Tool::Ray,
+        Tool::Channel,
+        Tool::FibRetracement,
+        Tool::FibExtension,
+        Tool::Rectangle,
+        Tool::Text,
+        Tool::Arrow,
+    ];
+
+    fn icon(self) -> &'static str {
+        match self {
+            Tool::Cursor => "➚",
+            Tool::TrendLine => "╱",
+            Tool::HorizontalLine => "─",
+            Tool::VerticalLine => "│",
+            Tool::Ray => "↗",
+            Tool::Channel => "▱",
+            Tool::FibRetracement => "Fib",
+            Tool::FibExtension => "Fib ext",
+            Tool::Rectangle => "▭",
+            Tool::Text => "T",
+            Tool::Arrow => "→",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Tool::Cursor => {
+                "Select and drag drawings, right-click one to edit it, Delete removes it"
+            }
+            Tool::TrendLine => "Trendline: click start and end",
+            Tool::HorizontalLine => "Horizontal line: click the price",
+            Tool::VerticalLine => "Vertical line: click the date",
+            Tool::Ray => "Ray: click the start and a point it extends through",
+            Tool::Channel => "Parallel channel: click start and end, then the width",
+            Tool::FibRetracement => "Fibonacci retracement: click the swing start and end",
+            Tool::FibExtension => {
+                "Fibonacci extension: click the swing start and end, then the projection point"
+            }
+            Tool::Rectangle => "Rectangle: click two opposite corners",
+            Tool::Text => "Text note: click where it goes",
+            Tool::Arrow => "Arrow: click the tail and the tip",
+        }
+    }
+
+    /// Clicks needed to place the drawing.
+    fn points(self) -> usize {
+        match self {
+            Tool::Cursor => 0,
+            Tool::HorizontalLine | Tool::VerticalLine | Tool::Text => 1,
+            Tool::TrendLine | Tool::Ray | Tool::FibRetracement | Tool::Rectangle | Tool::Arrow => 2,
+            Tool::Channel | Tool::FibExtension => 3,
+        }
+    }
+
+    /// The shape placed with `points`, which has one point per click.
+    fn build(self, points: &[Point], text: &str) -> Option<Shape> {
+        Some(match (self, points) {
+            (Tool::TrendLine, [start, end]) => Shape::TrendLine {
+                start: *start,
+                end: *end,
+            },
+            (Tool::HorizontalLine, [point]) => Shape::HorizontalLine { price: point[1] },
+            (Tool::VerticalLine, [point]) => Shape::VerticalLine { time: point[0] },
+            (Tool::Ray, [start, through]) => Shape::Ray {
+                start: *start,
+                through: *through,
+            },
+            (Tool::Channel, [start, end, width]) => Shape::Channel {
+                start: *start,
+                end: *end,
+                offset: width[1] - line_at(*start, *end, width[0]),
+            },
+            (Tool::FibRetracement, [start, end]) => Shape::FibRetracement {
+                start: *start,
+                end: *end,
+            },
+            (Tool::FibExtension, [start, end, projection]) => Shape::FibExtension {
+                start: *start,
+                end: *end,
+                projection: *projection,
+            },
+            (Tool::Rectangle, [corner, opposite]) => Shape::Rectangle {
+                corner: *corner,
+                opposite: *opposite,
+            },
+            (Tool::Text, [position]) => Shape::Text {
+                position: *position,
+                text: match text.trim() {
+                    "" => "Note".to_string(),
+                    text => text.to_string(),
+                },
+            },
+            (Tool::Arrow, [start, end]) => Shape::Arrow {
+                start: *start,
+                end: *end,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// A drawing being moved with the pointer.
+struct Drag {
+    index: usize,
+    /// The dragged handle, `None` moves the whole drawing.
+    handle: Option<usize>,
+    /// Where the drag started, in plot coordinates.
+    origin: Point,
+    /// The drawings before the drag, for undo.
+    before: Vec<Drawing>,
+}
+
+/// Drawing toolbar of a chart and the drawings placed with it.
+#[derive(Default)]
+pub struct DrawingTools {
+    pub drawings: Vec<Drawing>,
+    tool: Tool,
+    /// Points clicked so far for the drawing being placed.
+    points: Vec<Point>,
+    /// Text of the next note.
+    text: String,
+    selected: Option<usize>,
+    drag: Option<Drag>,
+    /// Earlier states of `drawings`, the latest last.
+    undo: Vec<Vec<Drawing>>,
+    redo: Vec<Vec<Drawing>>,
+    /// Transform of the last frame, drags are hit-tested before the plot is shown.
+    transform: Option<PlotTransform>,
+    /// Whether placed points and dragged handles snap to the bars.
+    magnet: bool,
+    /// Whether `drawings` changed since [`DrawingTools::take_changed`].
+    changed: bool,
+    /// Whether typing in the focused text field of the context menu was
+    /// recorded for undo.
+    text_recorded: bool,
+}
+
+impl DrawingTools {
+    pub fn new(drawings: Vec<Drawing>) -> Self {
+        DrawingTools {
+            drawings,
+            ..Default::default()
+        }
+    }
+
+    pub fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for tool in Tool::ALL {
+                if ui
+                    .selectable_label(self.tool == tool, tool.icon())
+                    .on_hover_text(tool.description())
+                    .clicked()
+                {
+                    self.select_tool(tool);
+                }
+            }
+
+            if self.tool == Tool::Text {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.text)
+                        .hint_text("Note")
+                        .desired_width(120.0),
+                );
+            }
+
+            ui.separator();
+
+            ui.toggle_value(&mut self.magnet, "Magnet")
+                .on_hover_text("Snap points to the open, high, low or close of the nearest bar");
+
+            ui.add_enabled(!self.undo.is_empty(), egui::Button::new("⟲"))
+                .on_hover_text("Undo (Ctrl+Z)")
+                .clicked()
+                .then(|| self.undo());
+
+            ui.add_enabled(!self.redo.is_empty(), egui::Button::new("⟳"))
+                .on_hover_text("Redo (Ctrl+Shift+Z)")
+                .clicked()
+                .then(|| self.redo());
+
+            ui.add_enabled(
+                !self.drawings.is_empty(),
+                egui::Button::new("Clear drawings"),
+            )
+            .clicked()
+            .then(|| {
+                self.record();
+                self.drawings.clear();
+                self.selected = None;
+            });
+        });
+    }
+
+    /// Shows `drawings` instead, e.g. after another chart of the symbol
+    /// changed them.
+    pub fn replace(&mut self, drawings: Vec<Drawing>) {
+        self.drawings = drawings;
+        self.selected = None;
+        self.drag = None;
+    }
+
+    /// Adds imported drawings, as one edit.
+    pub fn extend(&mut self, drawings: Vec<Drawing>) {
+        self.record();
+        self.drawings.extend(drawings);
+    }
+
+    /// Whether the drawings were edited since the last call.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    fn select_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.points.clear();
+        self.selected = None;
+    }
+
+    /// Remembers the drawings before an edit.
+    fn record(&mut self) {
+        self.push_undo(self.drawings.clone());
+    }
+
+    fn push_undo(&mut self, drawings: Vec<Drawing>) {
+        push_capped(&mut self.undo, drawings);
+        self.redo.clear();
+        self.changed = true;
+    }
+
+    fn undo(&mut self) {
+        if let Some(drawings) = self.undo.pop() {
+            self.redo
+                .push(std::mem::replace(&mut self.drawings, drawings));
+            self.selected = None;
+            self.drag = None;
+            self.changed = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(drawings) = self.redo.pop() {
+            let previous = std::mem::replace(&mut self.drawings, drawings);
+
+            push_capped(&mut self.undo, previous);
+            self.selected = None;
+            self.drag = None;
+            self.changed = true;
+        }
+    }
+
+    /// The topmost drawing under the screen position `pos`, with the handle
+    /// there if it is the selected one.
+    fn hit(&self, pos: Pos2) -> Option<(usize, Option<usize>)> {
+        let transform = self.transform.as_ref()?;
+
+        if let Some(index) = self.selected {
+            let handle = self
+                .drawings
+                .get(index)?
+                .shape
+                .handles()
+                .iter()
+                .position(|handle| {
+                    transform
+                        .position_from_point(&PlotPoint::new(handle[0], handle[1]))
+                        .distance(pos)
+                        <= HANDLE_DISTANCE
+                });
+
+            if handle.is_some() {
+                return Some((index, handle));
+            }
+        }
+
+        self.drawings
+            .iter()
+            .rposition(|drawing| drawing.shape.distance(pos, transform) <= HIT_DISTANCE)
+            .map(|index| (index, None))
+    }
+
+    /// Whether dragging may pan the plot, drags on drawings move them instead.
+    pub fn allow_drag(&self, ui: &egui::Ui) -> bool {
+        if self.drag.is_some() {
+            return false;
+        }
+
+        self.tool != Tool::Cursor
+            || ui
+                .input(|input| input.pointer.hover_pos())
+                .is_none_or(|pos| self.hit(pos).is_none())
+    }
+
+    /// The plot point at the screen position `pos`, in magnet mode snapped to
+    /// the nearest bar if `snapping`.
+    fn point(
+        &self,
+        pos: Pos2,
+        transform: &PlotTransform,
+        quotes: &[Quote],
+        snapping: bool,
+    ) -> Point {
+        let point = transform.value_from_position(pos);
+
+        match self.magnet && snapping {
+            true => snap([point.x, point.y], transform, quotes),
+            false => [point.x, point.y],
+        }
+    }
+
+    /// Places, selects, moves and edits drawings with the pointer and keyboard.
+    pub fn interact(
+        &mut self,
+        response: &egui::Response,
+        transform: PlotTransform,
+        quotes: &[Quote],
+    ) {
+        self.transform = Some(transform);
+
+        // Escape cancels a drawing in progress even after the pointer left.
+        if (response.hovered() || !self.points.is_empty()) && !response.ctx.wants_keyboard_input() {
+            self.keyboard(&response.ctx);
+        }
+
+        if response.clicked() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                match self.tool {
+                    Tool::Cursor => self.selected = self.hit(pos).map(|(index, _)| index),
+                    _ => self.place(self.point(pos, &transform, quotes, true)),
+                }
+            }
+        }
+
+        if response.secondary_clicked() && self.tool == Tool::Cursor {
+            if let Some(pos) = response.interact_pointer_pos() {
+                self.selected = self.hit(pos).map(|(index, _)| index);
+            }
+        }
+
+        if self.selected.is_some() {
+            response.context_menu(|ui| self.context_menu(ui));
+        }
+
+        self.drag(response, &transform, quotes);
+    }
+
+    fn keyboard(&mut self, ctx: &egui::Context) {
+        ctx.input_mut(|input| {
+            if input.consume_key(Modifiers::NONE, Key::Escape) {
+                match self.tool {
+                    Tool::Cursor => self.selected = None,
+                    _ => self.select_tool(Tool::Cursor),
+                }
+            }
+
+            if input.consume_key(Modifiers::NONE, Key::Delete)
+                || input.consume_key(Modifiers::NONE, Key::Backspace)
+            {
+                self.delete_selected();
+            }
+
+            if input.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z)
+                || input.consume_key(Modifiers::COMMAND, Key::Y)
+            {
+                self.redo();
+            } else if input.consume_key(Modifiers::COMMAND, Key::Z) {
+                self.undo();
+            }
+        });
+    }
+
+    fn drag(&mut self, response: &egui::Response, transform: &PlotTransform, quotes: &[Quote]) {
+        if response.drag_started_by(egui::PointerButton::Primary) && self.tool == Tool::Cursor {
+            if let Some(pos) = response.ctx.input(|input| input.pointer.press_origin()) {
+                self.start_drag(pos, transform, quotes);
+            }
+        }
+
+        if let Some(pos) = response.interact_pointer_pos() {
+            self.drag_to(pos, transform, quotes);
+        }
+
+        if !response.dragged() {
+            self.end_drag();
+        }
+    }
+
+    /// Starts moving the drawing or handle under the screen position `pos`.
+    fn start_drag(&mut self, pos: Pos2, transform: &PlotTransform, quotes: &[Quote]) {
+        let Some((index, handle)) = self.hit(pos) else {
+            return;
+        };
+
+        self.selected = Some(index);
+        self.drag = Some(Drag {
+            index,
+            handle,
+            origin: self.point(pos, transform, quotes, false),
+            before: self.drawings.clone(),
+        });
+    }
+
+    fn drag_to(&mut self, pos: Pos2, transform: &PlotTransform, quotes: &[Quote]) {
+        let Some(drag) = &self.drag else {
+            return;
+        };
+
+        // Handles snap, whole drawings keep their shape.
+        let point = self.point(pos, transform, quotes, drag.handle.is_some());
+        let mut drawing = drag.before[drag.index].clone();
+
+        match drag.handle {
+            Some(handle) => drawing.shape.move_handle(handle, point),
+            None => drawing
+                .shape
+                .translate([point[0] - drag.origin[0], point[1] - drag.origin[1]]),
+        }
+
+        self.drawings[drag.index] = drawing;
+    }
+
+    /// Finishes a drag as one edit.
+    fn end_drag(&mut self) {
+        let Some(drag) = self.drag.take() else {
+            return;
+        };
+
+        if drag.before != self.drawings {
+            self.push_undo(drag.before);
+        }
+    }
+
+    /// Places the next point of the drawing with the selected tool.
+    fn place(&mut self, point: Point) {
+        self.points.push(point);
+
+        if self.points.len() < self.tool.points() {
+            return;
+        }
+
+        let shape = self.tool.build(&self.points, &self.text);
+
+        self.select_tool(Tool::Cursor);
+
+        if let Some(shape) = shape {
+            self.record();
+            self.drawings.push(Drawing::new(shape));
+            self.selected = Some(self.drawings.len() - 1);
+        }
+    }
+
+    fn delete_selected(&mut self) {
+        if let Some(index) = self.selected.take() {
+            self.record();
+            self.drawings.remove(index);
+        }
+    }
+
+    fn context_menu(&mut self, ui: &mut egui::Ui) {
+        let Some(index) = self.selected else {
+            ui.close_menu();
+            return;
+        };
+
+        let mut drawing = self.drawings[index].clone();
+
+        ui.horizontal(|ui| {
+            ui.label("Color");
+
+            for color in PALETTE {
+                let (rect, response) =
+                    ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::click());
+
+                ui.painter().rect_filled(rect, 2.0, color);
+
+                if drawing.color == color {
+                    ui.painter()
+                        .rect_stroke(rect, 2.0, ui.visuals().selection.stroke);
+                }
+
+                if response.clicked() {
+                    drawing.color = color;
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Width");
+
+            for width in WIDTHS {
+                ui.selectable_value(&mut drawing.width, width, width.to_string());
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Style");
+
+            for dash in Dash::ALL {
+                ui.selectable_value(&mut drawing.dash, dash, dash.label());
+            }
+        });
+
+        if let Shape::Text { text, .. } = &mut drawing.shape {
+            let response = ui.text_edit_singleline(text);
+
+            // Typing is recorded once, at the first change while focused.
+            if response.changed() && !self.text_recorded {
+                self.record();
+                self.text_recorded = true;
+            }
+
+            self.text_recorded &= response.has_focus();
+        }
+
+        ui.separator();
+
+        if ui.button("Delete").clicked() {
+            self.delete_selected();
+            ui.close_menu();
+            return;
+        }
+
+        let current = &self.drawings[index];
+
+        if drawing != *current {
+            // Typing was recorded by the text field.
+            if drawing.shape == current.shape {
+                self.record();
+            }
+
+            self.drawings[index] = drawing;
+            self.changed = true;
+        }
+    }
+
+    /// Shows the drawings, the selected one highlighted with its handles, and
+    /// a preview of the one being placed.
+    pub fn show(&self, ui: &mut PlotUi, quotes: &[Quote]) {
+        let bars = match (quotes.first(), quotes.last()) {
+            (Some(first), Some(last)) => (first.timestamp as f64, last.timestamp as f64),
+            _ => (0.0, 0.0),
+        };
+
+        for (index, drawing) in self.drawings.iter().enumerate() {
+            if self.selected != Some(index) {
+                drawing.show(ui, bars);
+                continue;
+            }
+
+            Drawing {
+                width: drawing.width + 1.5,
+                ..drawing.clone()
+            }
+            .show(ui, bars);
+
+            ui.points(
+                Points::new(PlotPoints::new(drawing.shape.handles()))
+                    .shape(MarkerShape::Circle)
+                    .radius(4.0)
+                    .filled(true)
+                    .color(drawing.color),
+            );
+        }
+
+        let Some(pointer) = ui.pointer_coordinate() else {
+            return;
+        };
+
+        if self.tool == Tool::Cursor {
+            return;
+        }
+
+        let transform = *ui.transform();
+        let pointer = self.point(
+            transform.position_from_point(&pointer),
+            &transform,
+            quotes,
+            true,
+        );
+
+        if self.magnet {
+            ui.points(
+                Points::new(PlotPoints::new(vec![pointer]))
+                    .shape(MarkerShape::Circle)
+                    .radius(3.0)
+                    .color(DEFAULT_COLOR),
+            );
+        }
+
+        // Missing points follow the pointer until they are clicked.
+        let mut points = self.points.clone();
+
+        points.resize(self.tool.points(), pointer);
+
+        if let Some(shape) = self.tool.build(&points, &self.text) {
+            Drawing {
+                color: DEFAULT_COLOR.gamma_multiply(0.6),
+                ..Drawing::new(shape)
+            }
+            .show(ui, bars);
+        }
+    }
+}
+
+/// Pushes onto the undo stack, dropping the oldest entry beyond
+/// [`UNDO_LENGTH`].
+fn push_capped(stack: &mut Vec<Vec<Drawing>>, drawings: Vec<Drawing>) {
+    if stack.len() == UNDO_LENGTH {
+        stack.remove(0);
+    }
+
+    stack.push(drawings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One pixel per unit over 0 to 100 on both axes, y grows upwards.
+    fn transform() -> PlotTransform {
+        PlotTransform::new(
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(100.0, 100.0)),
+            PlotBounds::from_min_max([0.0, 0.0], [100.0, 100.0]),
+            false,
+            false,
+        )
+    }
+
+    fn screen(point: Point) -> Pos2 {
+        transform().position_from_point(&PlotPoint::new(point[0], point[1]))
+    }
+
+    fn tools(shapes: Vec<Shape>) -> DrawingTools {
+        DrawingTools {
+            transform: Some(transform()),
+            ..DrawingTools::new(shapes.into_iter().map(Drawing::new).collect())
+        }
+    }
+
+    fn shapes(tools: &DrawingTools) -> Vec<Shape> {
+        tools
+            .drawings
+            .iter()
+            .map(|drawing| drawing.shape.clone())
+            .collect()
+    }
+
+    fn trend_line() -> Shape {
+        Shape::TrendLine {
+            start: [10.0, 10.0],
+            end: [90.0, 90.0],
+        }
+    }
+
+    #[test]
+    fn hits_pick_the_topmost_drawing_near_the_pointer() {
+        let mut tools = tools(vec![trend_line(), Shape::HorizontalLine { price: 50.0 }]);
+
+        // Two pixels off the trendline, far from the horizontal line.
+        assert_eq!(tools.hit(screen([20.0, 22.0])), Some((0, None)));
+        // Both lines cross here, the later one is on top.
+        assert_eq!(tools.hit(screen([50.0, 50.0])), Some((1, None)));
+        // Ten pixels from either.
+        assert_eq!(tools.hit(screen([20.0, 40.0])), None);
+        // Beyond the end of the segment.
+        assert_eq!(tools.hit(screen([95.0, 95.0])), None);
+
+        // Handles only belong to the selected drawing.
+        assert_eq!(tools.hit(screen([10.0, 11.0])), Some((0, None)));
+
+        tools.selected = Some(0);
+
+        assert_eq!(tools.hit(screen([10.0, 11.0])), Some((0, Some(0))));
+        assert_eq!(tools.hit(screen([88.0, 90.0])), Some((0, Some(1))));
+    }
+
+    #[test]
+    fn shapes_translate_and_move_handles() {
+        let mut shape = Shape::Channel {
+            start: [10.0, 10.0],
+            end: [30.0, 30.0],
+            offset: 5.0,
+        };
+
+        shape.translate([10.0, -5.0]);
+
+        assert_eq!(
+            shape,
+            Shape::Channel {
+                start: [20.0, 5.0],
+                end: [40.0, 25.0],
+                offset: 5.0,
+            }
+        );
+
+        // The middle handle sets the offset from the line below it.
+        shape.move_handle(2, [30.0, 35.0]);
+        shape.move_handle(1, [50.0, 50.0]);
+
+        assert_eq!(
+            shape,
+            Shape::Channel {
+                start: [20.0, 5.0],
+                end: [50.0, 50.0],
+                offset: 20.0,
+            }
+        );
+
+        let mut line = Shape::HorizontalLine { price: 50.0 };
+
+        line.translate([10.0, 5.0]);
+
+        assert_eq!(line, Shape::HorizontalLine { price: 55.0 });
+    }
+
+    #[test]
+    fn undo_after_a_move_restores_the_original_points() {
+        let mut tools = tools(vec![trend_line()]);
+
+        tools.start_drag(screen([50.0, 50.0]), &transform(), &[]);
+        tools.drag_to(screen([55.0, 45.0]), &transform(), &[]);
+        tools.drag_to(screen([60.0, 40.0]), &transform(), &[]);
+        tools.end_drag();
+
+        let moved = || Shape::TrendLine {
+            start: [20.0, 0.0],
+            end: [100.0, 80.0],
+        };
+
+        assert_eq!(shapes(&tools), [moved()]);
+        assert_eq!(tools.undo.len(), 1, "a drag is one edit");
+
+        tools.undo();
+
+        assert_eq!(shapes(&tools), [trend_line()]);
+
+        tools.redo();
+
+        assert_eq!(shapes(&tools), [moved()]);
+        assert!(tools.redo.is_empty());
+    }
+
+    #[test]
+    fn dragging_a_handle_reshapes_the_drawing() {
+        let mut tools = tools(vec![trend_line()]);
+
+        tools.selected = Some(0);
+        tools.start_drag(screen([90.0, 90.0]), &transform(), &[]);
+        tools.drag_to(screen([70.0, 20.0]), &transform(), &[]);
+        tools.end_drag();
+
+        assert_eq!(
+            shapes(&tools),
+            [Shape::TrendLine {
+                start: [10.0, 10.0],
+                end: [70.0, 20.0],
+            }]
+        );
+
+        // A drag that ends where it started is no edit.
+        tools.start_drag(screen([10.0, 10.0]), &transform(), &[]);
+        tools.end_drag();
+
+        assert_eq!(tools.undo.len(), 1);
+    }
+
+    #[test]
+    fn a_new_edit_clears_the_redo_stack() {
+        let mut tools = tools(Vec::new());
+
+        tools.select_tool(Tool::HorizontalLine);
+        tools.place([0.0, 50.0]);
+        tools.select_tool(Tool::HorizontalLine);
+        tools.place([0.0, 60.0]);
+
+        tools.undo();
+
+        assert_eq!(shapes(&tools), [Shape::HorizontalLine { price: 50.0 }]);
+        assert_eq!(tools.redo.len(), 1);
+
+        tools.selected = Some(0);
+        tools.delete_selected();
+
+        assert!(tools.drawings.is_empty());
+        assert!(tools.redo.is_empty());
+
+        tools.undo();
+        tools.undo();
+
+        assert!(tools.drawings.is_empty());
+        assert!(tools.undo.is_empty());
+        assert_eq!(tools.redo.len(), 2);
+    }
+
+    #[test]
+    fn magnet_snaps_to_the_nearest_price_within_reach() {
+        let bar = |timestamp, open, high, low, close| Quote {
+            timestamp,
+            open,
+            high,
+            low,
+            volume: 100,
+            close,
+            adjclose: close,
+        };
+
+        let quotes = [
+            bar(20, 30.0, 40.0, 10.0, 35.0),
+            bar(60, 50.0, 70.0, 45.0, 65.0),
+        ];
+
+        // The high is two pixels away, the close three.
+        assert_eq!(snap([22.0, 38.0], &transform(), &quotes), [20.0, 40.0]);
+        assert_eq!(snap([25.0, 34.0], &transform(), &quotes), [20.0, 35.0]);
+        // Nearer to the second bar, whose low is in reach.
+        assert_eq!(snap([45.0, 47.0], &transform(), &quotes), [60.0, 45.0]);
+        assert_eq!(snap([55.0, 53.0], &transform(), &quotes), [60.0, 50.0]);
+
+        // Twenty and fifteen pixels above the highs.
+        assert_eq!(snap([22.0, 60.0], &transform(), &quotes), [22.0, 60.0]);
+        assert_eq!(snap([70.0, 85.0], &transform(), &quotes), [70.0, 85.0]);
+        assert_eq!(snap([22.0, 60.0], &transform(), &[]), [22.0, 60.0]);
+    }
+
+    #[test]
+    fn points_only_snap_in_magnet_mode() {
+        let quotes = [Quote {
+            timestamp: 20,
+            open: 30.0,
+            high: 40.0,
+            low: 10.0,
+            volume: 100,
+            close: 35.0,
+            adjclose: 35.0,
+        }];
+
+        let mut tools = tools(Vec::new());
+        let pos = screen([22.0, 38.0]);
+
+        assert_eq!(tools.point(pos, &transform(), &quotes, true), [22.0, 38.0]);
+
+        tools.magnet = true;
+
+        assert_eq!(tools.point(pos, &transform(), &quotes, true), [20.0, 40.0]);
+        assert_eq!(tools.point(pos, &transform(), &quotes, false), [22.0, 38.0]);
+    }
+
+    #[test]
+    fn undo_history_is_capped() {
+        let mut tools = tools(vec![trend_line()]);
+
+        for _ in 0..UNDO_LENGTH + 10 {
+            tools.record();
+        }
+
+        assert_eq!(tools.undo.len(), UNDO_LENGTH);
+
+        tools.undo();
+        tools.redo();
+
+        assert_eq!((tools.undo.len(), tools.redo.len()), (UNDO_LENGTH, 0));
+    }
+}
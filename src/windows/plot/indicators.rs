@@ -0,0 +1,326 @@
+use std::sync::Arc;
+
+use egui::Color32;
+use egui_plot::{Bar, BarChart, HLine, Line, PlotPoints, PlotUi};
+use serde::{Deserialize, Serialize};
+use yahoo_finance_api::Quote;
+
+use market_view::{
+    indicators::{Indicator, Series},
+    interval::Interval,
+};
+
+use super::date_plot;
+
+const PALETTE: [Color32; 6] = [
+    Color32::from_rgb(255, 200, 0),
+    Color32::from_rgb(0, 170, 255),
+    Color32::from_rgb(220, 80, 255),
+    Color32::from_rgb(255, 120, 60),
+    Color32::from_rgb(120, 230, 200),
+    Color32::from_rgb(240, 240, 240),
+];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IndicatorSettings {
+    pub indicator: Indicator,
+    pub color: Color32,
+    pub visible: bool,
+}
+
+struct ComputedIndicator {
+    label: String,
+    color: Color32,
+    is_overlay: bool,
+    series: Vec<Series>,
+}
+
+/// Per window list of indicators and their computed values.
+#[derive(Default)]
+pub struct IndicatorPanel {
+    pub indicators: Vec<IndicatorSettings>,
+    computed: Arc<Vec<ComputedIndicator>>,
+    timestamps: Arc<Vec<u64>>,
+    /// Length, first and last timestamp and last close of the quotes the
+    /// values were computed from.
+    computed_for: Option<(usize, u64, u64, u64)>,
+    dirty: bool,
+}
+
+impl IndicatorPanel {
+    pub fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Indicators", |ui| {
+            ui.menu_button("Add", |ui| {
+                for indicator in Indicator::defaults() {
+                    if ui.button(indicator.label()).clicked() {
+                        self.add(indicator);
+                        ui.close_menu();
+                    }
+                }
+            });
+
+            ui.separator();
+
+            let mut remove = None;
+
+            for (index, settings) in self.indicators.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    self.dirty |= ui.checkbox(&mut settings.visible, "").changed();
+                    self.dirty |= ui.color_edit_button_srgba(&mut settings.color).changed();
+
+                    ui.label(settings.indicator.kind());
+
+                    self.dirty |= parameter_controls(ui, &mut settings.indicator);
+
+                    if ui.button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                });
+            }
+
+            if let Some(index) = remove {
+                self.indicators.remove(index);
+                self.dirty = true;
+            }
+
+            if self.indicators.is_empty() {
+                ui.label("No indicators");
+            }
+        });
+    }
+
+    pub fn add(&mut self, indicator: Indicator) {
+        let color = PALETTE[self.indicators.len() % PALETTE.len()];
+
+        self.add_settings(IndicatorSettings {
+            indicator,
+            color,
+            visible: true,
+        });
+    }
+
+    pub fn add_settings(&mut self, settings: IndicatorSettings) {
+        self.indicators.push(settings);
+
+        self.dirty = true;
+    }
+
+    /// Recomputes the values if the quotes or any settings changed.
+    pub fn update(&mut self, quotes: &[Quote]) {
+        let signature = (
+            quotes.len(),
+            quotes
+                .first()
+                .map(|quote| quote.timestamp)
+                .unwrap_or_default(),
+            quotes
+                .last()
+                .map(|quote| quote.timestamp)
+                .unwrap_or_default(),
+            quotes
+                .last()
+                .map(|quote| quote.close.to_bits())
+                .unwrap_or_default(),
+        );
+
+        if !self.dirty && self.computed_for == Some(signature) {
+            return;
+        }
+
+        self.computed = Arc::new(
+            self.indicators
+                .iter()
+                .filter(|settings| settings.visible)
+                .map(|settings| ComputedIndicator {
+                    label: settings.indicator.label(),
+                    color: settings.color,
+                    is_overlay: settings.indicator.is_overlay(),
+                    series: settings.indicator.compute(quotes),
+                })
+                .collect(),
+        );
+        self.timestamps = Arc::new(quotes.iter().map(|quote| quote.timestamp).collect());
+        self.computed_for = Some(signature);
+        self.dirty = false;
+    }
+
+    pub fn pane_count(&self) -> usize {
+        self.computed
+            .iter()
+            .filter(|computed| !computed.is_overlay)
+            .count()
+    }
+
+    pub fn show_overlays(&self, ui: &mut PlotUi) {
+        for computed in self.computed.iter().filter(|computed| computed.is_overlay) {
+            for series in &computed.series {
+                ui.line(
+                    Line::new(self.points(&series.values))
+                        .color(computed.color)
+                        .name(format!("{} {}", computed.label, series.name)),
+                );
+            }
+        }
+    }
+
+    /// Shows one pane per oscillator, each with its x axis linked to `link_group`.
+    pub fn show_panes(
+        &self,
+        ui: &mut egui::Ui,
+        id: &str,
+        link_group: egui::Id,
+        interval: Interval,
+        height: f32,
+    ) {
+        let date_format = interval.date_format();
+        let bar_width = interval.seconds() as f64 * 0.5;
+
+        for (index, computed) in self
+            .computed
+            .iter()
+            .filter(|computed| !computed.is_overlay)
+            .enumerate()
+        {
+            date_plot(format!("{} indicator {}", id, index), date_format)
+                .height(height)
+                .link_axis(link_group, true, false)
+                .link_cursor(link_group, true, false)
+                .allow_zoom([true, false])
+                .allow_drag([true, false])
+                .auto_bounds([false, true].into())
+                .y_axis_label(computed.label.clone())
+                .show(ui, |ui| {
+                    for level in reference_levels(&computed.label) {
+                        ui.hline(HLine::new(*level).color(Color32::GRAY));
+                    }
+
+                    for (series_index, series) in computed.series.iter().enumerate() {
+                        let color = if series_index == 0 {
+                            computed.color
+                        } else {
+                            PALETTE[(series_index + 3) % PALETTE.len()]
+                        };
+
+                        if series.name == "Histogram" {
+                            ui.bar_chart(BarChart::new(
+                                self.timestamps
+                                    .iter()
+                                    .zip(&series.values)
+                                    .filter_map(|(timestamp, value)| {
+                                        Some(
+                                            Bar::new(*timestamp as f64, (*value)?)
+                                                .width(bar_width)
+                                                .fill(color.gamma_multiply(0.5)),
+                                        )
+                                    })
+                                    .collect(),
+                            ));
+                        } else {
+                            ui.line(
+                                Line::new(self.points(&series.values))
+                                    .color(color)
+                                    .name(series.name),
+                            );
+                        }
+                    }
+                });
+        }
+    }
+
+    /// Indicator values at `timestamp`, one line per series, for tooltips.
+    pub fn tooltip(&self) -> impl Fn(u64) -> String {
+        let computed = self.computed.clone();
+        let timestamps = self.timestamps.clone();
+
+        move |timestamp| {
+            let Ok(index) = timestamps.binary_search(&timestamp) else {
+                return String::new();
+            };
+
+            let mut text = String::new();
+
+            for computed in computed.iter() {
+                let values = computed
+                    .series
+                    .iter()
+                    .filter_map(|series| {
+                        Some(format!("{} {:.2}", series.name, series.values[index]?))
+                    })
+                    .collect::<Vec<_>>();
+
+                if !values.is_empty() {
+                    text += &format!("\n{}: {}", computed.label, values.join(", "));
+                }
+            }
+
+            text
+        }
+    }
+
+    fn points(&self, values: &[Option<f64>]) -> PlotPoints {
+        PlotPoints::new(
+            self.timestamps
+                .iter()
+                .zip(values)
+                .filter_map(|(timestamp, value)| Some([*timestamp as f64, (*value)?]))
+                .collect(),
+        )
+    }
+}
+
+fn reference_levels(label: &str) -> &'static [f64] {
+    if label.starts_with("RSI") {
+        &[30.0, 70.0]
+    } else if label.starts_with("Stoch") {
+        &[20.0, 80.0]
+    } else if label.starts_with("MACD") {
+        &[0.0]
+    } else {
+        &[]
+    }
+}
+
+/// Returns whether any parameter changed.
+fn parameter_controls(ui: &mut egui::Ui, indicator: &mut Indicator) -> bool {
+    let period = |ui: &mut egui::Ui, value: &mut usize| {
+        ui.add(egui::DragValue::new(value).clamp_range(1..=500))
+            .changed()
+    };
+
+    let mut changed = false;
+
+    match indicator {
+        Indicator::Sma { period: value }
+        | Indicator::Ema { period: value }
+        | Indicator::Wma { period: value }
+        | Indicator::Rsi { period: value }
+        | Indicator::Atr { period: value }
+        | Indicator::Adx { period: value } => changed |= period(ui, value),
+        Indicator::Bollinger {
+            period: value,
+            deviations,
+        } => {
+            changed |= period(ui, value);
+
+            changed |= ui
+                .add(
+                    egui::DragValue::new(deviations)
+                        .speed(0.1)
+                        .clamp_range(0.1..=10.0),
+                )
+                .changed();
+        }
+        Indicator::Macd { fast, slow, signal } => {
+            changed |= period(ui, fast);
+            changed |= period(ui, slow);
+            changed |= period(ui, signal);
+        }
+        Indicator::Stochastic { k, d } => {
+            changed |= period(ui, k);
+            changed |= period(ui, d);
+        }
+        Indicator::Vwap | Indicator::Obv => {}
+    }
+
+    changed
+}
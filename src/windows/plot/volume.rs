@@ -0,0 +1,126 @@
+use egui_plot::{Bar, BarChart, Line, PlotPoints};
+use serde::{Deserialize, Serialize};
+use yahoo_finance_api::Quote;
+
+use market_view::Interval;
+
+use super::date_plot;
+
+/// Volume histogram shown below the candlestick chart.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VolumePanel {
+    pub visible: bool,
+    pub show_average: bool,
+    pub average_period: usize,
+}
+
+impl Default for VolumePanel {
+    fn default() -> Self {
+        VolumePanel {
+            visible: true,
+            show_average: false,
+            average_period: 20,
+        }
+    }
+}
+
+impl VolumePanel {
+    pub fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.visible, "Volume");
+
+        if self.visible {
+            ui.checkbox(&mut self.show_average, "MA");
+
+            if self.show_average {
+                ui.add(egui::DragValue::new(&mut self.average_period).clamp_range(1..=500));
+            }
+        }
+    }
+
+    /// Shows the histogram with its x axis linked to `link_group`.
+    pub fn show(
+        &self,
+        ui: &mut egui::Ui,
+        id: &str,
+        link_group: egui::Id,
+        quotes: &[Quote],
+        interval: Interval,
+        height: f32,
+    ) {
+        let date_format = interval.date_format();
+        let bar_width = interval.seconds() as f64 * 0.5;
+
+        let bars = quotes
+            .iter()
+            .map(|quote| {
+                let color = if quote.open < quote.close {
+                    egui::Color32::from_rgb(0, 255, 0)
+                } else {
+                    egui::Color32::from_rgb(255, 0, 0)
+                };
+
+                Bar::new(quote.timestamp as f64, quote.volume as f64)
+                    .width(bar_width)
+                    .fill(color)
+                    .stroke(egui::Stroke::new(1.0, color))
+            })
+            .collect::<Vec<_>>();
+
+        let chart = BarChart::new(bars).element_formatter(Box::new(move |bar, _| {
+            format!(
+                "Date: {}\nVolume: {}",
+                chrono::DateTime::from_timestamp(bar.argument as i64, 0)
+                    .unwrap()
+                    .format(date_format),
+                bar.value as u64
+            )
+        }));
+
+        date_plot(format!("{} volume", id), date_format)
+            .height(height)
+            .link_axis(link_group, true, false)
+            .link_cursor(link_group, true, false)
+            .allow_zoom([true, false])
+            .allow_drag([true, false])
+            .auto_bounds([false, true].into())
+            .include_y(0.0)
+            .y_axis_formatter(|gridmark: egui_plot::GridMark, _, _| format_volume(gridmark.value))
+            .show(ui, |ui| {
+                ui.bar_chart(chart);
+
+                if self.show_average {
+                    ui.line(
+                        Line::new(PlotPoints::new(moving_average(quotes, self.average_period)))
+                            .color(egui::Color32::LIGHT_BLUE)
+                            .name(format!("MA {}", self.average_period)),
+                    );
+                }
+            });
+    }
+}
+
+fn moving_average(quotes: &[Quote], period: usize) -> Vec<[f64; 2]> {
+    quotes
+        .windows(period.max(1))
+        .map(|window| {
+            let sum = window.iter().map(|quote| quote.volume as f64).sum::<f64>();
+
+            [
+                window.last().unwrap().timestamp as f64,
+                sum / window.len() as f64,
+            ]
+        })
+        .collect()
+}
+
+fn format_volume(volume: f64) -> String {
+    if volume >= 1e9 {
+        format!("{:.1}B", volume / 1e9)
+    } else if volume >= 1e6 {
+        format!("{:.1}M", volume / 1e6)
+    } else if volume >= 1e3 {
+        format!("{:.1}K", volume / 1e3)
+    } else {
+        format!("{:.0}", volume)
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use egui_extras::{Column, TableBuilder};
+use egui_plot::Line;
+
+use market_view::{
+    portfolio::{CostBasis, Ledger, Position, Transaction, TransactionKind},
+    Interval, Storage,
+};
+
+use super::{plot::date_plot, watchlist::format_value, ViewWindow};
+
+/// How often prices and the equity curve are rebuilt from the storage.
+const VALUATION_INTERVAL: Duration = Duration::from_secs(2);
+
+const KINDS: [&str; 4] = ["Buy", "Sell", "Dividend", "Fee"];
+
+/// Input fields for a new transaction.
+struct TransactionForm {
+    date: String,
+    symbol: String,
+    kind: usize,
+    quantity: f64,
+    price: f64,
+    amount: f64,
+    commission: f64,
+    error: Option<String>,
+}
+
+impl Default for TransactionForm {
+    fn default() -> Self {
+        TransactionForm {
+            date: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+            symbol: String::new(),
+            kind: 0,
+            quantity: 1.0,
+            price: 0.0,
+            amount: 0.0,
+            commission: 0.0,
+            error: None,
+        }
+    }
+}
+
+impl TransactionForm {
+    fn transaction(&self) -> anyhow::Result<Transaction> {
+        let symbol = self.symbol.trim().to_uppercase();
+
+        anyhow::ensure!(!symbol.is_empty(), "Symbol is empty");
+
+        let timestamp = chrono::NaiveDate::parse_from_str(self.date.trim(), "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("Date must be YYYY-MM-DD"))?
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp()
+            .max(0) as u64;
+
+        let kind = match self.kind {
+            0 => TransactionKind::Buy {
+                quantity: self.quantity,
+                price: self.price,
+            },
+            1 => TransactionKind::Sell {
+                quantity: self.quantity,
+                price: self.price,
+            },
+            2 => TransactionKind::Dividend {
+                amount: self.amount,
+            },
+            _ => TransactionKind::Fee {
+                amount: self.amount,
+            },
+        };
+
+        Ok(Transaction {
+            timestamp,
+            symbol,
+            kind,
+            commission: self.commission,
+        })
+    }
+}
+
+pub struct PortfolioWindow {
+    storage: Storage,
+    ledger: Ledger,
+    positions: Vec<Position>,
+    prices: HashMap<String, f64>,
+    equity: Vec<[f64; 2]>,
+    form: TransactionForm,
+    last_valuation: Option<Instant>,
+}
+
+impl PortfolioWindow {
+    pub fn new(storage: Storage, ledger: Ledger) -> Self {
+        PortfolioWindow {
+            storage,
+            positions: ledger.positions(),
+            ledger,
+            prices: HashMap::new(),
+            equity: Vec::new(),
+            form: TransactionForm::default(),
+            last_valuation: None,
+        }
+    }
+
+    /// Symbols of the ledger, the scheduler keeps their daily bars fresh.
+    pub fn symbols(&self) -> Vec<String> {
+        self.ledger.symbols()
+    }
+
+    fn revalue(&mut self) {
+        let quotes = self
+            .ledger
+            .symbols()
+            .into_iter()
+            .filter_map(|symbol| {
+                let quotes = self.storage.get_quotes(&symbol, Interval::OneDay)?;
+
+                Some((symbol, quotes))
+            })
+            .collect::<HashMap<_, _>>();
+
+        self.prices = quotes
+            .iter()
+            .filter_map(|(symbol, quotes)| Some((symbol.clone(), quotes.last()?.close)))
+            .collect();
+
+        self.positions = self.ledger.positions();
+        self.equity = self.ledger.equity_curve(&quotes);
+        self.last_valuation = Some(Instant::now());
+    }
+
+    /// Saves the ledger and revalues with the fresh transactions.
+    fn ledger_changed(&mut self) {
+        self.ledger.save();
+        self.last_valuation = None;
+    }
+
+    fn holdings(&self, ui: &mut egui::Ui) {
+        let columns = [
+            "Symbol",
+            "Quantity",
+            "Avg cost",
+            "Last",
+            "Value",
+            "Unrealized",
+            "Realized",
+            "Dividends",
+            "Fees",
+        ];
+
+        let mut total_value = 0.0;
+        let mut total_unrealized = 0.0;
+        let mut total_income = 0.0;
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .columns(Column::auto().at_least(60.0), columns.len())
+            .header(20.0, |mut header| {
+                for title in columns {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|mut body| {
+                for position in &self.positions {
+                    let price = self.prices.get(&position.symbol).copied();
+
+                    total_value += price.map_or(0.0, |price| position.market_value(price));
+                    total_unrealized += price.map_or(0.0, |price| position.unrealized(price));
+                    total_income += position.income();
+
+                    body.row(20.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(&position.symbol);
+                        });
+                        row.col(|ui| {
+                            ui.label(format!("{}", position.quantity));
+                        });
+                        row.col(|ui| {
+                            ui.label(format_value(position.average_cost()));
+                        });
+                        row.col(|ui| {
+                            ui.label(format_value(price));
+                        });
+                        row.col(|ui| {
+                            ui.label(format_value(
+                                price.map(|price| position.market_value(price)),
+                            ));
+                        });
+                        row.col(|ui| {
+                            profit_label(ui, price.map(|price| position.unrealized(price)));
+                        });
+                        row.col(|ui| {
+                            profit_label(ui, Some(position.realized));
+                        });
+                        row.col(|ui| {
+                            ui.label(format_value(Some(position.dividends)));
+                        });
+                        row.col(|ui| {
+                            ui.label(format_value(Some(position.fees)));
+                        });
+                    });
+                }
+            });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Value: {:.2}", total_value));
+            ui.separator();
+            ui.label("Unrealized:");
+            profit_label(ui, Some(total_unrealized));
+            ui.separator();
+            ui.label("Realized incl. dividends and fees:");
+            profit_label(ui, Some(total_income));
+        });
+    }
+
+    fn transactions(&mut self, ui: &mut egui::Ui) {
+        let form = &mut self.form;
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut form.date).desired_width(80.0));
+            ui.add(
+                egui::TextEdit::singleline(&mut form.symbol)
+                    .hint_text("Symbol")
+                    .desired_width(60.0),
+            );
+
+            egui::ComboBox::from_id_source("Transaction kind")
+                .width(80.0)
+                .show_index(ui, &mut form.kind, KINDS.len(), |index| KINDS[index]);
+
+            if form.kind < 2 {
+                ui.label("Qty");
+                ui.add(egui::DragValue::new(&mut form.quantity).clamp_range(0.0..=f64::MAX));
+                ui.label("Price");
+                ui.add(
+                    egui::DragValue::new(&mut form.price)
+                        .speed(0.01)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+            } else {
+                ui.label("Amount");
+                ui.add(
+                    egui::DragValue::new(&mut form.amount)
+                        .speed(0.01)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+            }
+
+            ui.label("Commission");
+            ui.add(
+                egui::DragValue::new(&mut form.commission)
+                    .speed(0.01)
+                    .clamp_range(0.0..=f64::MAX),
+            );
+        });
+
+        let mut changed = false;
+
+        if ui.button("Add transaction").clicked() {
+            match self.form.transaction() {
+                Ok(transaction) => {
+                    self.ledger.add(transaction);
+                    self.form.error = None;
+                    changed = true;
+                }
+                Err(e) => self.form.error = Some(e.to_string()),
+            }
+        }
+
+        if let Some(error) = &self.form.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        let mut remove = None;
+
+        egui::ScrollArea::vertical()
+            .id_source("Transactions")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for (index, transaction) in self.ledger.transactions.iter().enumerate().rev() {
+                    ui.horizontal(|ui| {
+                        ui.label(describe(transaction));
+
+                        if ui.small_button("🗑").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                }
+            });
+
+        if let Some(index) = remove {
+            self.ledger.remove(index);
+            changed = true;
+        }
+
+        if changed {
+            self.ledger_changed();
+        }
+    }
+}
+
+impl ViewWindow for PortfolioWindow {
+    fn view(&mut self, ui: &mut eframe::egui::Ui) {
+        if self
+            .last_valuation
+            .is_none_or(|last_valuation| last_valuation.elapsed() >= VALUATION_INTERVAL)
+        {
+            self.revalue();
+        }
+
+        ui.ctx().request_repaint_after(VALUATION_INTERVAL);
+
+        egui::Window::new("Portfolio")
+            .default_open(false)
+            .show(ui.ctx(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Cost basis");
+
+                    let mut cost_basis = self.ledger.cost_basis;
+
+                    for method in CostBasis::ALL {
+                        ui.selectable_value(&mut cost_basis, method, method.to_string());
+                    }
+
+                    if cost_basis != self.ledger.cost_basis {
+                        self.ledger.cost_basis = cost_basis;
+                        self.ledger_changed();
+                    }
+                });
+
+                self.holdings(ui);
+
+                ui.collapsing("Transactions", |ui| self.transactions(ui));
+
+                date_plot("Equity curve", Interval::OneDay.date_format())
+                    .height(200.0)
+                    .show(ui, |ui| {
+                        ui.line(
+                            Line::new(self.equity.clone())
+                                .color(egui::Color32::LIGHT_BLUE)
+                                .name("Equity"),
+                        );
+                    });
+            });
+    }
+}
+
+fn describe(transaction: &Transaction) -> String {
+    let date = chrono::DateTime::from_timestamp(transaction.timestamp as i64, 0)
+        .unwrap_or_default()
+        .format("%Y-%m-%d");
+
+    let details = match transaction.kind {
+        TransactionKind::Buy { quantity, price } | TransactionKind::Sell { quantity, price } => {
+            format!("{} @ {:.2}", quantity, price)
+        }
+        TransactionKind::Dividend { amount } | TransactionKind::Fee { amount } => {
+            format!("{:.2}", amount)
+        }
+    };
+
+    format!(
+        "{} {} {} {} (commission {:.2})",
+        date,
+        transaction.kind.name(),
+        transaction.symbol,
+        details,
+        transaction.commission
+    )
+}
+
+fn profit_label(ui: &mut egui::Ui, value: Option<f64>) {
+    let color = match value {
+        Some(value) if value > 0.0 => egui::Color32::from_rgb(0, 255, 0),
+        Some(value) if value < 0.0 => egui::Color32::from_rgb(255, 0, 0),
+        _ => egui::Color32::GRAY,
+    };
+
+    ui.colored_label(color, format_value(value));
+}
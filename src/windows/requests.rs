@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use market_view::requests::{Outcome, RequestLog, RequestRecord};
+
+use super::ViewWindow;
+
+/// How often the list is redrawn while the window is open.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Outcomes of the latest provider requests.
+pub struct RequestsWindow {
+    log: RequestLog,
+}
+
+impl RequestsWindow {
+    pub fn new(log: RequestLog) -> Self {
+        RequestsWindow { log }
+    }
+
+    fn summary(ui: &mut egui::Ui, records: &[RequestRecord]) {
+        let count = |matches: fn(&Outcome) -> bool| {
+            records
+                .iter()
+                .filter(|record| matches(&record.outcome))
+                .count()
+        };
+
+        let retried = records.iter().filter(|record| record.attempts > 1).count();
+
+        ui.label(format!(
+            "{} succeeded, {} failed, {} timed out, {} joined, {} retried",
+            count(|outcome| *outcome == Outcome::Succeeded),
+            count(|outcome| matches!(outcome, Outcome::Failed(_))),
+            count(|outcome| *outcome == Outcome::TimedOut),
+            count(|outcome| *outcome == Outcome::Joined),
+            retried
+        ));
+    }
+}
+
+impl ViewWindow for RequestsWindow {
+    fn view(&mut self, ui: &mut eframe::egui::Ui) {
+        let response = egui::Window::new("Requests")
+            .default_open(false)
+            .show(ui.ctx(), |ui| {
+                let records = self.log.records();
+
+                Self::summary(ui, &records);
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("Request log").striped(true).show(ui, |ui| {
+                            ui.strong("Time");
+                            ui.strong("Request");
+                            ui.strong("Attempts");
+                            ui.strong("Duration");
+                            ui.strong("Outcome");
+                            ui.end_row();
+
+                            for record in records.iter().rev() {
+                                let time = chrono::DateTime::<chrono::Local>::from(record.finished)
+                                    .format("%H:%M:%S");
+
+                                ui.label(time.to_string());
+                                ui.label(format!("{} {}", record.provider, record.description));
+                                ui.label(record.attempts.to_string());
+                                ui.label(format!("{:.2} s", record.elapsed.as_secs_f64()));
+
+                                match &record.outcome {
+                                    Outcome::Succeeded => ui.label("ok"),
+                                    Outcome::Joined => ui.label("joined"),
+                                    Outcome::TimedOut => ui.colored_label(
+                                        egui::Color32::from_rgb(255, 0, 0),
+                                        "timed out",
+                                    ),
+                                    Outcome::Failed(message) => ui
+                                        .colored_label(egui::Color32::from_rgb(255, 0, 0), "failed")
+                                        .on_hover_text(message),
+                                };
+
+                                ui.end_row();
+                            }
+                        });
+
+                        if records.is_empty() {
+                            ui.label("No requests yet");
+                        }
+                    });
+            });
+
+        if response.is_some_and(|response| response.inner.is_some()) {
+            ui.ctx().request_repaint_after(REFRESH_INTERVAL);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::time::{Duration, SystemTime};
+
+use market_view::scheduler::{RefreshSettings, ScheduleEntry, Scheduler};
+
+use super::ViewWindow;
+
+/// How often the queue is redrawn while the window is open.
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Settings and queue of the auto-refresh scheduler.
+pub struct SchedulerWindow {
+    scheduler: Scheduler,
+    settings: RefreshSettings,
+}
+
+impl SchedulerWindow {
+    pub const STORAGE_KEY: &'static str = "refresh";
+
+    /// Starts `scheduler` with the settings stored by eframe.
+    pub fn new(scheduler: Scheduler, storage: Option<&dyn eframe::Storage>) -> Self {
+        let settings: RefreshSettings = storage
+            .and_then(|storage| eframe::get_value(storage, Self::STORAGE_KEY))
+            .unwrap_or_default();
+
+        scheduler.set_settings(settings.clone());
+        scheduler.start();
+
+        SchedulerWindow {
+            scheduler,
+            settings,
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Self::STORAGE_KEY, &self.settings);
+    }
+
+    /// Series to keep fresh, replaces the previous ones.
+    pub fn set_targets(&self, targets: impl IntoIterator<Item = (String, market_view::Interval)>) {
+        self.scheduler.set_targets(targets);
+    }
+
+    fn settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.enabled, "Enabled");
+
+            ui.checkbox(&mut settings.pause_outside_hours, "Pause outside hours")
+                .on_hover_text(
+                    "Intraday bars wait for the session, daily bars refresh after the close",
+                );
+
+            ui.add(time_of_day(&mut settings.hours.open));
+            ui.label("-");
+            ui.add(time_of_day(&mut settings.hours.close));
+
+            if ui.button("Refresh now").clicked() {
+                self.scheduler.refresh_now();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Every");
+
+            for interval in RefreshSettings::fetched_intervals() {
+                let mut seconds = settings.cadence(interval).as_secs();
+
+                ui.label(interval.as_str());
+
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut seconds)
+                            .clamp_range(10..=24 * 60 * 60)
+                            .suffix(" s"),
+                    )
+                    .changed()
+                {
+                    settings.cadence.insert(interval, seconds);
+                }
+            }
+        });
+
+        if *settings != self.scheduler.settings() {
+            self.scheduler.set_settings(settings.clone());
+        }
+    }
+
+    fn queue(&self, ui: &mut egui::Ui) {
+        let queue = self.scheduler.queue();
+
+        if queue.is_empty() {
+            ui.label("Open a chart, add symbols to a watchlist, a transaction or an alert to refresh them");
+            return;
+        }
+
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                egui::Grid::new("Refresh queue")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Symbol");
+                        ui.strong("Interval");
+                        ui.strong("Last run");
+                        ui.strong("Next run");
+                        ui.end_row();
+
+                        for entry in &queue {
+                            ui.label(&entry.symbol);
+                            ui.label(entry.interval.as_str());
+                            match &entry.error {
+                                Some(error) => ui
+                                    .colored_label(
+                                        egui::Color32::from_rgb(255, 0, 0),
+                                        format!("{} (failed)", last_run(entry)),
+                                    )
+                                    .on_hover_text(error.to_string()),
+                                None => ui.label(last_run(entry)),
+                            };
+                            ui.label(next_run(entry, self.settings.enabled));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
+
+impl ViewWindow for SchedulerWindow {
+    fn view(&mut self, ui: &mut eframe::egui::Ui) {
+        let response = egui::Window::new("Auto refresh")
+            .default_open(false)
+            .show(ui.ctx(), |ui| {
+                self.settings(ui);
+
+                ui.separator();
+
+                self.queue(ui);
+            });
+
+        if response.is_some_and(|response| response.inner.is_some()) {
+            ui.ctx().request_repaint_after(STATUS_INTERVAL);
+        }
+    }
+}
+
+/// Edits minutes after midnight as `HH:MM`.
+fn time_of_day(minutes: &mut u32) -> egui::DragValue<'_> {
+    egui::DragValue::new(minutes)
+        .clamp_range(0..=24 * 60)
+        .speed(5.0)
+        .custom_formatter(|value, _| format!("{:02}:{:02}", value as u32 / 60, value as u32 % 60))
+        .custom_parser(|text| {
+            let (hours, minutes) = text.split_once(':')?;
+
+            Some(hours.trim().parse::<f64>().ok()? * 60.0 + minutes.trim().parse::<f64>().ok()?)
+        })
+}
+
+fn last_run(entry: &ScheduleEntry) -> String {
+    let Some(last_run) = entry.last_run else {
+        return "never".to_string();
+    };
+
+    let time = chrono::DateTime::<chrono::Local>::from(last_run).format("%H:%M:%S");
+
+    match entry.fetched || entry.error.is_some() {
+        true => format!("{}", time),
+        false => format!("{} (fresh)", time),
+    }
+}
+
+fn next_run(entry: &ScheduleEntry, enabled: bool) -> String {
+    if entry.running {
+        return "running".to_string();
+    }
+
+    if !enabled {
+        return "disabled".to_string();
+    }
+
+    match entry.next_run {
+        Some(next_run) => match next_run.duration_since(SystemTime::now()) {
+            Ok(wait) => format!("in {} s", wait.as_secs()),
+            Err(_) => "due".to_string(),
+        },
+        None => "paused".to_string(),
+    }
+}
@@ -1,30 +1,71 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use yahoo_finance_api::{Quote, YQuoteItem};
 
-use crate::{
-    app::{RUNTIME, STORAGE},
-    requests,
-};
+use market_view::{DataError, Interval, Storage};
+
+use crate::annotations::Annotations;
 
-use super::{PlotWindow, ViewWindow};
+use super::{notifications::error_banner, PlotWindow, ViewWindow};
 
 pub struct SearchWindow {
+    storage: Storage,
     search_string: String,
-    search_results: Arc<Mutex<Vec<YQuoteItem>>>,
+    search_results: Arc<Mutex<Result<Vec<YQuoteItem>, DataError>>>,
     selected_symbol: Option<String>,
     selected_symbol_history: Arc<Mutex<Option<Vec<Quote>>>>,
     plot_windows: Arc<Mutex<Vec<PlotWindow>>>,
+    annotations: Annotations,
 }
 
 impl SearchWindow {
-    pub fn new(plot_windows: Arc<Mutex<Vec<PlotWindow>>>) -> Self {
+    pub fn new(
+        storage: Storage,
+        plot_windows: Arc<Mutex<Vec<PlotWindow>>>,
+        annotations: Annotations,
+    ) -> Self {
         SearchWindow {
+            storage,
             search_string: String::new(),
-            search_results: Arc::new(Mutex::new(Vec::new())),
+            search_results: Arc::new(Mutex::new(Ok(Vec::new()))),
             selected_symbol: None,
             selected_symbol_history: Arc::new(Mutex::new(None)),
             plot_windows,
+            annotations,
+        }
+    }
+
+    fn search(&self, ctx: &egui::Context) {
+        let search_string = self.search_string.clone();
+        let search_results = self.search_results.clone();
+        let storage = self.storage.clone();
+        let ctx = ctx.clone();
+
+        self.storage.runtime().spawn(async move {
+            *search_results.lock().unwrap() = storage.search(&search_string).await;
+
+            ctx.request_repaint();
+        });
+    }
+
+    /// Shows why the selected symbol could not be loaded.
+    fn selected_symbol_error(&mut self, ui: &mut egui::Ui) {
+        let Some(selected_symbol) = self.selected_symbol.clone() else {
+            return;
+        };
+
+        let snapshot = self.storage.snapshot();
+
+        let Some(error) = snapshot.error(&selected_symbol, Interval::OneDay) else {
+            return;
+        };
+
+        if error_banner(ui, &format!("Loading {}", selected_symbol), error) {
+            self.storage
+                .refresh_quotes(&selected_symbol, Interval::OneDay, Duration::ZERO);
         }
     }
 }
@@ -40,30 +81,30 @@ impl ViewWindow for SearchWindow {
                         let response = ui.text_edit_singleline(&mut self.search_string);
 
                         if response.lost_focus() {
-                            let search_string = self.search_string.clone();
-                            
-                            let search_results = self.search_results.clone();
+                            self.search(ui.ctx());
+                        }
 
-                            RUNTIME.spawn(async move {
-                                let results = requests::search( &search_string)
-                                    .await
-                                    .unwrap_or_default();
+                        let error = self.search_results.lock().unwrap().as_ref().err().cloned();
 
-                                *search_results.lock().unwrap() = results;
-                            });
+                        if let Some(error) = error {
+                            if error_banner(ui, "Search", &error) {
+                                self.search(ui.ctx());
+                            }
                         }
 
+                        self.selected_symbol_error(ui);
+
                         let search_results = self.search_results.lock().unwrap();
 
-                        for result in search_results.iter() {
+                        for result in search_results.iter().flatten() {
                             let response = ui.button(result.long_name.clone());
 
                             if response.clicked() {
                                 self.selected_symbol = Some(result.symbol.clone());
-                                
+
                                 let symbol = result.symbol.clone();
 
-                                STORAGE.update_quotes_checked(&symbol);
+                                self.storage.update_quotes_checked(&symbol, Interval::OneDay);
                             }
 
                             response.on_hover_text(format!(
@@ -78,7 +119,7 @@ impl ViewWindow for SearchWindow {
                     });
 
                     if let Some(selected_symbol) = &self.selected_symbol {
-                        let history = STORAGE.get_quotes(selected_symbol);
+                        let history = self.storage.get_quotes(selected_symbol, Interval::OneDay);
 
                         if let Some(history) = history {
                             *self.selected_symbol_history.lock().unwrap() = Some(history);
@@ -86,8 +127,13 @@ impl ViewWindow for SearchWindow {
                     }
 
                     if let Some(history) = self.selected_symbol_history.lock().unwrap().take() {
-                        let plot_window =
-                            PlotWindow::new(self.selected_symbol.clone().unwrap(), history);
+                        let plot_window = PlotWindow::new(
+                            self.storage.clone(),
+                            self.annotations.clone(),
+                            self.selected_symbol.clone().unwrap(),
+                            Interval::OneDay,
+                            history,
+                        );
 
                         self.plot_windows.lock().unwrap().push(plot_window);
 
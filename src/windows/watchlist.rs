@@ -0,0 +1,509 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use egui_extras::{Column, TableBuilder};
+use serde::{Deserialize, Serialize};
+use yahoo_finance_api::Quote;
+
+use market_view::{storage::Subscription, Interval, Storage};
+
+use crate::annotations::Annotations;
+
+use super::{PlotWindow, ViewWindow};
+
+const DEFAULT_LIST: &str = "Watchlist";
+
+/// How often rows are rebuilt from the storage, picks up finished fetches.
+const ROWS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Number of closes shown in a sparkline.
+const SPARKLINE_BARS: usize = 30;
+
+/// Named symbol lists, persisted through eframe's storage.
+#[derive(Serialize, Deserialize)]
+pub struct Watchlists {
+    pub current: String,
+    pub lists: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Watchlists {
+    fn default() -> Self {
+        Watchlists {
+            current: DEFAULT_LIST.to_string(),
+            lists: BTreeMap::from([(DEFAULT_LIST.to_string(), Vec::new())]),
+        }
+    }
+}
+
+impl Watchlists {
+    pub const STORAGE_KEY: &'static str = "watchlists";
+
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, Self::STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Self::STORAGE_KEY, self);
+    }
+
+    pub fn symbols(&self) -> &[String] {
+        self.lists
+            .get(&self.current)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortColumn {
+    Symbol,
+    Last,
+    Change,
+    PercentChange,
+    Volume,
+}
+
+struct Row {
+    symbol: String,
+    last: Option<f64>,
+    change: Option<f64>,
+    percent_change: Option<f64>,
+    volume: Option<u64>,
+    closes: Vec<f64>,
+}
+
+impl Row {
+    /// The change is measured from the close of the last daily bar of an
+    /// earlier exchange day than the latest quote, which replaces the bar of
+    /// its day.
+    fn new(symbol: &str, quotes: &[Quote], latest: Option<&Quote>, utc_offset: i32) -> Self {
+        let day = |quote: &Quote| (quote.timestamp as i64 + utc_offset as i64).div_euclid(86_400);
+
+        let last = latest.or(quotes.last());
+        let previous = last.and_then(|last| {
+            quotes[..quotes.partition_point(|quote| day(quote) < day(last))].last()
+        });
+
+        let change = last
+            .zip(previous)
+            .map(|(last, previous)| last.close - previous.close);
+
+        let percent_change = change
+            .zip(previous)
+            .filter(|(_, previous)| previous.close != 0.0)
+            .map(|(change, previous)| change / previous.close * 100.0);
+
+        Row {
+            symbol: symbol.to_string(),
+            last: last.map(|quote| quote.close),
+            change,
+            percent_change,
+            volume: last.map(|quote| quote.volume),
+            closes: quotes
+                .iter()
+                .rev()
+                .take(SPARKLINE_BARS)
+                .rev()
+                .map(|quote| quote.close)
+                .collect(),
+        }
+    }
+}
+
+pub struct WatchlistWindow {
+    storage: Storage,
+    pub watchlists: Watchlists,
+    rows: Vec<Row>,
+    last_rows: Option<Instant>,
+    sort: Option<(SortColumn, bool)>,
+    new_symbol: String,
+    new_list: String,
+    plot_windows: Arc<Mutex<Vec<PlotWindow>>>,
+    annotations: Annotations,
+    /// Latest quote per symbol, the last price in the rows.
+    latest: Arc<Mutex<HashMap<String, Quote>>>,
+    /// Daily bars of the listed symbols, the latest quote is fetched again
+    /// whenever the scheduler refreshed them.
+    subscriptions: HashMap<String, Subscription>,
+}
+
+impl WatchlistWindow {
+    pub fn new(
+        storage: Storage,
+        watchlists: Watchlists,
+        plot_windows: Arc<Mutex<Vec<PlotWindow>>>,
+        annotations: Annotations,
+    ) -> Self {
+        WatchlistWindow {
+            storage,
+            watchlists,
+            rows: Vec::new(),
+            last_rows: None,
+            sort: None,
+            new_symbol: String::new(),
+            new_list: String::new(),
+            plot_windows,
+            annotations,
+            latest: Arc::default(),
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    /// Fetches the daily bars of the list right away, outside the schedule.
+    fn refresh_now(&self) {
+        for symbol in self.watchlists.symbols() {
+            self.storage
+                .refresh_quotes(symbol, Interval::OneDay, Duration::ZERO);
+        }
+    }
+
+    /// Fetches the latest quote of new symbols and of those whose daily bars
+    /// changed since the last call.
+    fn follow_latest(&mut self) {
+        let symbols = self.watchlists.symbols();
+
+        self.subscriptions
+            .retain(|symbol, _| symbols.contains(symbol));
+
+        for symbol in symbols {
+            let changed = match self.subscriptions.get_mut(symbol) {
+                Some(subscription) => subscription.changed(),
+                None => {
+                    let subscription = self.storage.subscribe(symbol, Interval::OneDay, || {});
+
+                    self.subscriptions.insert(symbol.clone(), subscription);
+
+                    true
+                }
+            };
+
+            if changed {
+                self.fetch_latest(symbol);
+            }
+        }
+    }
+
+    fn fetch_latest(&self, symbol: &str) {
+        let storage = self.storage.clone();
+        let latest = self.latest.clone();
+        let symbol = symbol.to_string();
+
+        self.storage.runtime().spawn(async move {
+            if let Ok(quote) = storage.latest_quote(&symbol).await {
+                latest.lock().unwrap().insert(symbol, quote);
+            }
+        });
+    }
+
+    fn update_rows(&mut self) {
+        self.follow_latest();
+
+        let latest = self.latest.lock().unwrap().clone();
+
+        self.rows = self
+            .watchlists
+            .symbols()
+            .iter()
+            .map(|symbol| {
+                let quotes = self
+                    .storage
+                    .get_quotes(symbol, Interval::OneDay)
+                    .unwrap_or_default();
+
+                Row::new(
+                    symbol,
+                    &quotes,
+                    latest.get(symbol),
+                    self.storage.get_utc_offset(symbol),
+                )
+            })
+            .collect();
+
+        self.sort_rows();
+        self.last_rows = Some(Instant::now());
+    }
+
+    fn sort_rows(&mut self) {
+        let Some((column, ascending)) = self.sort else {
+            return;
+        };
+
+        self.rows.sort_by(|a, b| {
+            let ordering = match column {
+                SortColumn::Symbol => a.symbol.cmp(&b.symbol),
+                SortColumn::Last => a
+                    .last
+                    .partial_cmp(&b.last)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                SortColumn::Change => a
+                    .change
+                    .partial_cmp(&b.change)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                SortColumn::PercentChange => a
+                    .percent_change
+                    .partial_cmp(&b.percent_change)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                SortColumn::Volume => a.volume.cmp(&b.volume),
+            };
+
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+
+    /// Marks the rows as outdated.
+    fn invalidate(&mut self) {
+        self.last_rows = None;
+    }
+
+    fn open_plot(&self, symbol: &str) {
+        let plot_window = PlotWindow::open(
+            self.storage.clone(),
+            self.annotations.clone(),
+            symbol.to_string(),
+            Interval::OneDay,
+        );
+
+        self.plot_windows.lock().unwrap().push(plot_window);
+    }
+
+    fn list_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut selected = self.watchlists.current.clone();
+
+            egui::ComboBox::from_id_source("Watchlist")
+                .selected_text(&selected)
+                .show_ui(ui, |ui| {
+                    for name in self.watchlists.lists.keys() {
+                        ui.selectable_value(&mut selected, name.clone(), name);
+                    }
+                });
+
+            if selected != self.watchlists.current {
+                self.watchlists.current = selected;
+                self.invalidate();
+            }
+
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_list)
+                    .hint_text("List name")
+                    .desired_width(100.0),
+            );
+
+            let name = self.new_list.trim().to_string();
+
+            ui.add_enabled(!name.is_empty(), egui::Button::new("New"))
+                .clicked()
+                .then(|| {
+                    self.watchlists.lists.entry(name.clone()).or_default();
+                    self.watchlists.current = name;
+                    self.new_list.clear();
+                    self.invalidate();
+                });
+
+            ui.add_enabled(self.watchlists.lists.len() > 1, egui::Button::new("Delete"))
+                .on_hover_text("Delete the current list")
+                .clicked()
+                .then(|| {
+                    self.watchlists.lists.remove(&self.watchlists.current);
+                    self.watchlists.current = self.watchlists.lists.keys().next().cloned().unwrap();
+                    self.invalidate();
+                });
+        });
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.new_symbol)
+                    .hint_text("Symbol")
+                    .desired_width(100.0),
+            );
+
+            let submitted =
+                response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+
+            if ui.button("Add").clicked() || submitted {
+                let symbol = self.new_symbol.trim().to_uppercase();
+
+                if let Some(symbols) = self.watchlists.lists.get_mut(&self.watchlists.current) {
+                    if !symbol.is_empty() && !symbols.contains(&symbol) {
+                        symbols.push(symbol);
+                    }
+                }
+
+                self.new_symbol.clear();
+                self.invalidate();
+            }
+
+            if ui.button("⟳").on_hover_text("Refresh").clicked() {
+                self.refresh_now();
+                self.invalidate();
+            }
+        });
+    }
+
+    fn table(&mut self, ui: &mut egui::Ui) {
+        let mut clicked_column = None;
+        let mut open = None;
+        let mut remove = None;
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .column(Column::auto().at_least(70.0))
+            .columns(Column::auto().at_least(60.0), 4)
+            .column(Column::exact(80.0))
+            .column(Column::auto())
+            .header(20.0, |mut header| {
+                for (column, title) in [
+                    (SortColumn::Symbol, "Symbol"),
+                    (SortColumn::Last, "Last"),
+                    (SortColumn::Change, "Change"),
+                    (SortColumn::PercentChange, "%"),
+                    (SortColumn::Volume, "Volume"),
+                ] {
+                    header.col(|ui| {
+                        let title = match self.sort {
+                            Some((sorted, true)) if sorted == column => format!("{} ⏶", title),
+                            Some((sorted, false)) if sorted == column => format!("{} ⏷", title),
+                            _ => title.to_string(),
+                        };
+
+                        if ui.button(title).clicked() {
+                            clicked_column = Some(column);
+                        }
+                    });
+                }
+
+                header.col(|ui| {
+                    ui.strong("Trend");
+                });
+                header.col(|_| {});
+            })
+            .body(|mut body| {
+                for row in &self.rows {
+                    let color = match row.change {
+                        Some(change) if change > 0.0 => egui::Color32::from_rgb(0, 255, 0),
+                        Some(change) if change < 0.0 => egui::Color32::from_rgb(255, 0, 0),
+                        _ => egui::Color32::GRAY,
+                    };
+
+                    body.row(20.0, |mut table_row| {
+                        table_row.col(|ui| {
+                            ui.selectable_label(false, &row.symbol)
+                                .on_hover_text("Double-click to open a chart")
+                                .double_clicked()
+                                .then(|| open = Some(row.symbol.clone()));
+                        });
+                        table_row.col(|ui| {
+                            ui.label(format_value(row.last));
+                        });
+                        table_row.col(|ui| {
+                            ui.colored_label(color, format_value(row.change));
+                        });
+                        table_row.col(|ui| {
+                            ui.colored_label(color, format_value(row.percent_change));
+                        });
+                        table_row.col(|ui| {
+                            ui.label(
+                                row.volume
+                                    .map(|volume| volume.to_string())
+                                    .unwrap_or_else(|| "-".to_string()),
+                            );
+                        });
+                        table_row.col(|ui| {
+                            sparkline(ui, &row.closes, color);
+                        });
+                        table_row.col(|ui| {
+                            ui.small_button("🗑")
+                                .clicked()
+                                .then(|| remove = Some(row.symbol.clone()));
+                        });
+                    });
+                }
+            });
+
+        if let Some(column) = clicked_column {
+            self.sort = match self.sort {
+                Some((sorted, ascending)) if sorted == column => Some((column, !ascending)),
+                _ => Some((column, true)),
+            };
+
+            self.sort_rows();
+        }
+
+        if let Some(symbol) = open {
+            self.open_plot(&symbol);
+        }
+
+        if let Some(symbol) = remove {
+            if let Some(symbols) = self.watchlists.lists.get_mut(&self.watchlists.current) {
+                symbols.retain(|listed| listed != &symbol);
+            }
+
+            self.rows.retain(|row| row.symbol != symbol);
+        }
+    }
+}
+
+impl ViewWindow for WatchlistWindow {
+    fn view(&mut self, ui: &mut eframe::egui::Ui) {
+        if self
+            .last_rows
+            .is_none_or(|last_rows| last_rows.elapsed() >= ROWS_INTERVAL)
+        {
+            self.update_rows();
+        }
+
+        ui.ctx().request_repaint_after(ROWS_INTERVAL);
+
+        egui::Window::new("Watchlist").show(ui.ctx(), |ui| {
+            self.list_controls(ui);
+
+            ui.separator();
+
+            self.table(ui);
+        });
+    }
+}
+
+/// Two decimals, or a dash for a missing value.
+pub(super) fn format_value(value: Option<f64>) -> String {
+    value
+        .map(|value| format!("{:.2}", value))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn sparkline(ui: &mut egui::Ui, closes: &[f64], color: egui::Color32) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(80.0, 16.0), egui::Sense::hover());
+
+    let (min, max) = closes
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(min, max), close| {
+            (min.min(*close), max.max(*close))
+        });
+
+    if closes.len() < 2 || max <= min {
+        return;
+    }
+
+    let points = closes
+        .iter()
+        .enumerate()
+        .map(|(index, close)| {
+            egui::pos2(
+                rect.left() + rect.width() * index as f32 / (closes.len() - 1) as f32,
+                rect.bottom() - rect.height() * ((close - min) / (max - min)) as f32,
+            )
+        })
+        .collect();
+
+    ui.painter()
+        .add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+}
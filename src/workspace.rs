@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::windows::ChartState;
+
+pub const DEFAULT_WORKSPACE: &str = "Default";
+
+/// Everything needed to bring back a set of open charts.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Workspace {
+    pub charts: Vec<ChartState>,
+}
+
+/// Named workspaces, persisted through eframe's storage.
+#[derive(Serialize, Deserialize)]
+pub struct Workspaces {
+    pub current: String,
+    pub saved: BTreeMap<String, Workspace>,
+}
+
+impl Default for Workspaces {
+    fn default() -> Self {
+        Workspaces {
+            current: DEFAULT_WORKSPACE.to_string(),
+            saved: BTreeMap::from([(DEFAULT_WORKSPACE.to_string(), Workspace::default())]),
+        }
+    }
+}
+
+impl Workspaces {
+    pub const STORAGE_KEY: &'static str = "workspaces";
+
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, Self::STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Self::STORAGE_KEY, self);
+    }
+
+    pub fn current(&self) -> Workspace {
+        self.saved.get(&self.current).cloned().unwrap_or_default()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.saved.keys().cloned().collect()
+    }
+}
@@ -0,0 +1,198 @@
+use std::{sync::Arc, time::Duration};
+
+use market_view::{
+    alerts::{AlertBook, Condition},
+    providers::FakeProvider,
+    storage::QuotesStorage,
+    Interval, Storage,
+};
+use yahoo_finance_api::Quote;
+
+const DAY: u64 = 86_400;
+
+fn quote(timestamp: u64, close: f64) -> Quote {
+    Quote {
+        timestamp,
+        open: close,
+        high: close,
+        low: close,
+        volume: 1,
+        close,
+        adjclose: close,
+    }
+}
+
+/// Daily bars closing at `closes`, starting at day one.
+fn bars(closes: &[f64]) -> Vec<Quote> {
+    closes
+        .iter()
+        .enumerate()
+        .map(|(index, close)| quote((index as u64 + 1) * DAY, *close))
+        .collect()
+}
+
+/// Evaluates the alerts on every prefix of `closes` as if the bars arrived
+/// one by one, returns the timestamps of the bars that triggered.
+fn triggers(book: &mut AlertBook, closes: &[f64]) -> Vec<u64> {
+    let quotes = bars(closes);
+
+    for end in 1..=quotes.len() {
+        book.evaluate("TEST", Interval::OneDay, &quotes[..end]);
+    }
+
+    book.history
+        .iter()
+        .map(|event| event.bar_timestamp)
+        .collect()
+}
+
+#[test]
+fn levels_trigger_when_crossed_in_either_direction() {
+    let mut book = AlertBook::default();
+
+    book.add(
+        "TEST",
+        Interval::OneDay,
+        Condition::CrossesLevel { level: 10.0 },
+    );
+
+    // Up through the level, stays above, down onto it, bounces off from below.
+    let triggered = triggers(&mut book, &[9.0, 11.0, 12.0, 10.0, 9.5, 9.9]);
+
+    assert_eq!(triggered, [2 * DAY, 4 * DAY]);
+    assert_eq!(book.history[0].message, "Close 11.00 crossed 10.00");
+}
+
+#[test]
+fn trendlines_are_extended_to_the_latest_bar() {
+    let mut book = AlertBook::default();
+
+    // Rises by one per day from 10 on day one.
+    book.add(
+        "TEST",
+        Interval::OneDay,
+        Condition::CrossesTrendline {
+            start: [DAY as f64, 10.0],
+            end: [2.0 * DAY as f64, 11.0],
+        },
+    );
+
+    // The line is at 12, 13 and 14 on the last three bars.
+    let triggered = triggers(&mut book, &[12.0, 12.5, 12.5, 12.8, 14.5]);
+
+    assert_eq!(triggered, [4 * DAY, 5 * DAY]);
+}
+
+#[test]
+fn alerts_rearm_unless_they_trigger_once() {
+    let mut rearming = AlertBook::default();
+    let mut once = AlertBook::default();
+
+    for book in [&mut rearming, &mut once] {
+        book.add(
+            "TEST",
+            Interval::OneDay,
+            Condition::CrossesLevel { level: 10.0 },
+        );
+    }
+
+    once.alerts[0].once = true;
+
+    let closes = [9.0, 11.0, 9.0, 11.0];
+
+    assert_eq!(
+        triggers(&mut rearming, &closes),
+        [2 * DAY, 3 * DAY, 4 * DAY]
+    );
+    assert!(rearming.alerts[0].enabled);
+
+    assert_eq!(triggers(&mut once, &closes), [2 * DAY]);
+    assert!(!once.alerts[0].enabled);
+    assert!(once.watched().is_empty());
+}
+
+#[test]
+fn cooldown_suppresses_triggers_after_the_last_one() {
+    let mut book = AlertBook::default();
+
+    book.add(
+        "TEST",
+        Interval::OneDay,
+        Condition::PercentMove { percent: 5.0 },
+    )
+    .cooldown = 2 * DAY;
+
+    // Every bar moves 10%, the two bars after a trigger are quiet.
+    let closes = [100.0, 110.0, 121.0, 133.1, 146.41, 161.051, 177.1561];
+
+    assert_eq!(triggers(&mut book, &closes), [2 * DAY, 5 * DAY]);
+}
+
+#[test]
+fn each_bar_triggers_only_once() {
+    let mut book = AlertBook::default();
+
+    book.add(
+        "TEST",
+        Interval::OneDay,
+        Condition::CrossesLevel { level: 10.0 },
+    );
+
+    let quotes = bars(&[9.0, 11.0]);
+
+    assert!(book.evaluate("TEST", Interval::OneDay, &quotes));
+    assert!(!book.evaluate("TEST", Interval::OneDay, &quotes));
+
+    // Other series are left alone.
+    assert!(!book.evaluate("OTHER", Interval::OneDay, &bars(&[9.0, 11.0])));
+    assert!(!book.evaluate("TEST", Interval::OneWeek, &quotes));
+
+    assert_eq!(book.history.len(), 1);
+}
+
+#[test]
+fn alerts_are_evaluated_on_update() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let storage = Storage::new(
+        Arc::new(FakeProvider::default()),
+        runtime.handle().clone(),
+        QuotesStorage::default(),
+        AlertBook::default(),
+    );
+
+    let fetch = || {
+        runtime
+            .block_on(storage.update("FAKE", Interval::OneDay, Duration::ZERO))
+            .unwrap();
+
+        storage
+            .snapshot()
+            .series("FAKE", Interval::OneDay)
+            .unwrap_or_default()
+    };
+
+    let quotes = fetch();
+    let [.., previous, current] = &quotes[..] else {
+        panic!("Not enough quotes");
+    };
+
+    let level = (previous.close + current.close) / 2.0;
+
+    storage.modify_alerts(|alerts| {
+        alerts.add("FAKE", Interval::OneDay, Condition::CrossesLevel { level });
+        alerts.add("TEST", Interval::OneDay, Condition::CrossesLevel { level });
+    });
+
+    fetch();
+
+    let alerts = storage.alerts();
+
+    assert_eq!(alerts.history.len(), 1);
+    assert_eq!(alerts.history[0].symbol, "FAKE");
+    assert_eq!(alerts.history[0].bar_timestamp, current.timestamp);
+}
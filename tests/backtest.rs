@@ -0,0 +1,165 @@
+use market_view::backtest::{
+    self, BacktestConfig, BarContext, Commission, Fill, Order, RsiReversion, Slippage, Strategy,
+};
+use yahoo_finance_api::Quote;
+
+/// Seconds per year the backtest annualizes with.
+const YEAR: u64 = 31_557_600;
+
+fn quote(timestamp: u64, open: f64, close: f64) -> Quote {
+    Quote {
+        timestamp,
+        open,
+        high: open.max(close),
+        low: open.min(close),
+        volume: 100,
+        close,
+        adjclose: close,
+    }
+}
+
+/// Places the listed orders on the close of each bar.
+struct Script(Vec<Vec<Order>>);
+
+impl Strategy for Script {
+    fn on_bar(&mut self, context: &BarContext) -> Vec<Order> {
+        self.0
+            .get(context.quotes.len() - 1)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "{} is not {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn scripted_round_trip_pays_commission_and_slippage() {
+    let quotes = [
+        quote(0, 10.0, 10.0),
+        quote(YEAR, 10.0, 12.0),
+        quote(2 * YEAR, 11.0, 11.0),
+    ];
+
+    let config = BacktestConfig {
+        initial_cash: 1000.0,
+        commission: Commission {
+            per_order: 1.0,
+            rate: 0.001,
+        },
+        slippage: Slippage::Fixed(0.5),
+    };
+
+    // The sell asks for more than is held.
+    let mut script = Script(vec![vec![Order::Buy(50.0)], vec![Order::Sell(80.0)]]);
+
+    let report = backtest::run(&quotes, &mut script, &config);
+
+    // Both fills at the next open moved by half a point against the trade,
+    // 50 × 10.5 = 525 traded each time for 1 + 0.1% × 525 = 1.525.
+    assert_eq!(
+        report.fills,
+        [
+            Fill {
+                timestamp: YEAR,
+                order: Order::Buy(50.0),
+                price: 10.5,
+                commission: 1.525,
+            },
+            Fill {
+                timestamp: 2 * YEAR,
+                order: Order::Sell(50.0),
+                price: 10.5,
+                commission: 1.525,
+            },
+        ]
+    );
+
+    // Cash 1000 − 525 − 1.525 = 473.475 plus 50 shares at 12, then
+    // 473.475 + 525 − 1.525 = 996.95 in cash.
+    let equity = report
+        .equity
+        .iter()
+        .map(|[_, value]| *value)
+        .collect::<Vec<_>>();
+
+    assert_close(equity[0], 1000.0);
+    assert_close(equity[1], 1073.475);
+    assert_close(equity[2], 996.95);
+
+    assert_eq!(report.trades.len(), 1);
+    assert_close(report.trades[0].profit, -3.05);
+
+    let statistics = report.statistics;
+
+    assert_close(statistics.total_return, -0.00305);
+    // Over two years.
+    assert_close(statistics.cagr, 0.99695_f64.sqrt() - 1.0);
+    assert_close(statistics.max_drawdown, 76.525 / 1073.475);
+    assert_eq!(statistics.win_rate, 0.0);
+
+    // One return per year, so the annualization factor is one.
+    let returns: [f64; 2] = [0.073475, 996.95 / 1073.475 - 1.0];
+    let mean = (returns[0] + returns[1]) / 2.0;
+    let deviation = ((returns[0] - mean).powi(2) + (returns[1] - mean).powi(2)).sqrt();
+
+    assert_close(statistics.sharpe, mean / deviation);
+}
+
+#[test]
+fn buys_are_capped_by_the_cash() {
+    let quotes = [quote(0, 10.0, 10.0), quote(YEAR, 10.0, 11.0)];
+
+    let config = BacktestConfig {
+        initial_cash: 100.0,
+        commission: Commission::default(),
+        slippage: Slippage::Relative(0.01),
+    };
+
+    let report = backtest::run(&quotes, &mut Script(vec![vec![Order::Buy(20.0)]]), &config);
+
+    // 99 left after the commission buys 9 shares at 10.1.
+    assert_eq!(report.fills.len(), 1);
+    assert_eq!(report.fills[0].order, Order::Buy(9.0));
+    assert_close(report.fills[0].price, 10.1);
+    assert_close(report.equity[1][1], 100.0 - 9.0 * 10.1 - 1.0 + 9.0 * 11.0);
+    assert!(report.trades.is_empty());
+}
+
+#[test]
+fn rsi_reversion_trades_on_the_indicator_values() {
+    let quotes = [10.0, 9.0, 8.0, 9.0, 10.0, 11.0]
+        .into_iter()
+        .enumerate()
+        .map(|(index, close)| quote(index as u64 * YEAR, close, close))
+        .collect::<Vec<_>>();
+
+    let config = BacktestConfig {
+        initial_cash: 100.0,
+        commission: Commission {
+            per_order: 0.0,
+            rate: 0.0,
+        },
+        slippage: Slippage::Fixed(0.0),
+    };
+
+    // RSI(2) is 0 on the third bar, then 50, 75 and 87.5.
+    let report = backtest::run(&quotes, &mut RsiReversion::new(2, 30.0, 70.0), &config);
+
+    let fills = report
+        .fills
+        .iter()
+        .map(|fill| (fill.timestamp, fill.order))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        fills,
+        [(3 * YEAR, Order::Buy(11.0)), (5 * YEAR, Order::Sell(11.0))]
+    );
+}
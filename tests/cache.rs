@@ -0,0 +1,114 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use market_view::{
+    cache::{encode_symbol, QuotesCache},
+    Interval,
+};
+use yahoo_finance_api::Quote;
+
+/// An empty directory for one test.
+fn directory(name: &str) -> PathBuf {
+    let directory =
+        std::env::temp_dir().join(format!("market-view-cache-{}-{}", std::process::id(), name));
+
+    let _ = std::fs::remove_dir_all(&directory);
+
+    directory
+}
+
+fn quotes() -> Vec<Quote> {
+    (0..3)
+        .map(|index| Quote {
+            timestamp: 1_700_000_000 + index * 86_400,
+            open: 10.0 + index as f64,
+            high: 11.5 + index as f64,
+            low: 9.25 + index as f64,
+            volume: 1_000 * index,
+            close: 10.75 + index as f64,
+            adjclose: 10.5 + index as f64,
+        })
+        .collect()
+}
+
+#[test]
+fn saved_series_load_back() {
+    let directory = directory("round-trip");
+    let cache = QuotesCache::new(directory.clone());
+    let last_update = UNIX_EPOCH + Duration::from_secs(1_700_500_000);
+
+    cache
+        .save("BRK/B", Interval::OneDay, -18_000, last_update, &quotes())
+        .unwrap();
+    cache
+        .save(
+            "BRK_B",
+            Interval::OneHour,
+            3_600,
+            last_update,
+            &quotes()[..1],
+        )
+        .unwrap();
+
+    let mut loaded = cache.load_all();
+
+    loaded.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+    // The symbols would share a file if `/` and `_` were both replaced.
+    assert_eq!(loaded.len(), 2);
+
+    assert_eq!(loaded[0].symbol, "BRK/B");
+    assert_eq!(loaded[0].interval, Interval::OneDay);
+    assert_eq!(loaded[0].utc_offset, -18_000);
+    assert_eq!(loaded[0].last_update, last_update);
+    assert_eq!(loaded[0].quotes, quotes());
+
+    assert_eq!(loaded[1].symbol, "BRK_B");
+    assert_eq!(loaded[1].interval, Interval::OneHour);
+    assert_eq!(loaded[1].quotes, quotes()[..1]);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn corrupt_files_are_skipped() {
+    let directory = directory("corrupt");
+    let cache = QuotesCache::new(directory.clone());
+
+    cache
+        .save("GOOD", Interval::OneDay, 0, UNIX_EPOCH, &quotes())
+        .unwrap();
+    cache
+        .save("BAD", Interval::OneDay, 0, UNIX_EPOCH, &quotes())
+        .unwrap();
+
+    // Claim a huge number of bars, reading must fail instead of allocating.
+    let path = directory.join("BAD_1d.bin");
+    let mut bytes = std::fs::read(&path).unwrap();
+    let count = 4 + 2 + 3 + 1 + 2 + 4 + 8;
+
+    bytes[count..count + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+
+    std::fs::write(directory.join("SHORT_1d.bin"), b"MVQ3\x05").unwrap();
+    std::fs::write(directory.join("OTHER_1d.bin"), b"not a cache file").unwrap();
+
+    let loaded = cache.load_all();
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].symbol, "GOOD");
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn symbols_are_encoded_as_file_names() {
+    assert_eq!(encode_symbol("BRK-B"), "BRK-B");
+    assert_eq!(encode_symbol("^GSPC"), "^GSPC");
+    assert_eq!(encode_symbol("EURUSD=X"), "EURUSD=X");
+    assert_eq!(encode_symbol("BTC/USD"), "BTC%2FUSD");
+    assert_eq!(encode_symbol("A B"), "A%20B");
+    assert_eq!(encode_symbol("%2F"), "%252F");
+}
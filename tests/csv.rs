@@ -0,0 +1,220 @@
+use std::path::PathBuf;
+
+use market_view::{
+    providers::{CsvColumns, CsvProvider, History, MarketDataProvider},
+    DataError, Interval,
+};
+use yahoo_finance_api::time::OffsetDateTime;
+
+/// An empty directory for one test.
+fn directory(name: &str) -> PathBuf {
+    let directory =
+        std::env::temp_dir().join(format!("market-view-csv-{}-{}", std::process::id(), name));
+
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+
+    directory
+}
+
+fn history(provider: &CsvProvider, symbol: &str, start: i64, end: i64) -> anyhow::Result<History> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(provider.history(
+        symbol,
+        OffsetDateTime::from_unix_timestamp(start).unwrap(),
+        OffsetDateTime::from_unix_timestamp(end).unwrap(),
+        Interval::OneDay,
+    ))
+}
+
+/// Timestamp, open, high, low, close, volume and adjclose of each bar.
+fn bars(history: &History) -> Vec<(u64, f64, f64, f64, f64, u64, f64)> {
+    history
+        .quotes
+        .iter()
+        .map(|q| {
+            (
+                q.timestamp,
+                q.open,
+                q.high,
+                q.low,
+                q.close,
+                q.volume,
+                q.adjclose,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn rows_are_parsed_and_sorted() {
+    let directory = directory("parse");
+
+    std::fs::write(
+        directory.join("TEST.csv"),
+        " Timestamp ,OPEN,High,Low,Close,Volume\n\
+         2024-01-03,2,3,1,2.5,200\n\
+         1704153600,1,2,0.5,1.5,100\n\
+         2024-01-04 12:00:00,3,4,2,3.5,300\n\
+         2024-01-05T00:00:00+01:00,4,5,3,4.5,400\n",
+    )
+    .unwrap();
+
+    let provider = CsvProvider::new(directory, CsvColumns::default(), None);
+    let history = history(&provider, "TEST", 0, i64::from(u32::MAX)).unwrap();
+
+    // Without an adjclose column it falls back to the close.
+    assert_eq!(
+        bars(&history),
+        [
+            (1_704_153_600, 1.0, 2.0, 0.5, 1.5, 100, 1.5),
+            (1_704_240_000, 2.0, 3.0, 1.0, 2.5, 200, 2.5),
+            (1_704_369_600, 3.0, 4.0, 2.0, 3.5, 300, 3.5),
+            (1_704_409_200, 4.0, 5.0, 3.0, 4.5, 400, 4.5),
+        ]
+    );
+    assert_eq!(history.utc_offset, 0);
+
+    // Only bars in the requested range.
+    let history = self::history(&provider, "TEST", 1_704_240_000, 1_704_369_600).unwrap();
+
+    assert_eq!(history.quotes.len(), 2);
+}
+
+#[test]
+fn columns_are_mapped_by_header() {
+    let directory = directory("columns");
+
+    std::fs::write(
+        directory.join("TEST.csv"),
+        "Date,Open,High,Low,Close,Adj Close\n\
+         2024-01-02,1,2,0.5,1.5,1.25\n",
+    )
+    .unwrap();
+
+    let columns = CsvColumns::parse("timestamp=Date, adjclose=Adj Close").unwrap();
+
+    assert_eq!(columns.timestamp, "Date");
+    assert_eq!(columns.adjclose, "Adj Close");
+    assert_eq!(columns.close, "close");
+
+    let provider = CsvProvider::new(directory.clone(), columns, None);
+
+    assert_eq!(
+        bars(&history(&provider, "TEST", 0, i64::from(u32::MAX)).unwrap()),
+        [(1_704_153_600, 1.0, 2.0, 0.5, 1.5, 0, 1.25)]
+    );
+
+    // The default timestamp column is missing.
+    let provider = CsvProvider::new(directory, CsvColumns::default(), None);
+    let error = history(&provider, "TEST", 0, i64::from(u32::MAX)).unwrap_err();
+
+    assert!(
+        error.to_string().contains("no column timestamp"),
+        "{}",
+        error
+    );
+
+    assert!(CsvColumns::parse("timestamp").is_err());
+    assert!(CsvColumns::parse("time=Date").is_err());
+}
+
+#[test]
+fn malformed_rows_name_their_line() {
+    let directory = directory("malformed");
+
+    std::fs::write(
+        directory.join("NUMBER.csv"),
+        "timestamp,open,high,low,close\n\
+         2024-01-02,1,2,0.5,1.5\n\
+         2024-01-03,1,two,0.5,1.5\n",
+    )
+    .unwrap();
+
+    std::fs::write(
+        directory.join("DATE.csv"),
+        "timestamp,open,high,low,close\n\
+         02/01/2024,1,2,0.5,1.5\n",
+    )
+    .unwrap();
+
+    // Dates before 1970 would wrap around to the far future.
+    for (symbol, date) in [
+        ("OLD_DATE", "1969-12-31"),
+        ("OLD_TIME", "1969-12-31 23:59:59"),
+        ("OLD_RFC3339", "1970-01-01T00:30:00+01:00"),
+    ] {
+        std::fs::write(
+            directory.join(format!("{}.csv", symbol)),
+            format!(
+                "timestamp,open,high,low,close\n\
+                 2024-01-02,1,2,0.5,1.5\n\
+                 {},1,2,0.5,1.5\n",
+                date
+            ),
+        )
+        .unwrap();
+    }
+
+    let provider = CsvProvider::new(directory, CsvColumns::default(), None);
+
+    for (symbol, message) in [
+        ("NUMBER", "NUMBER.csv:3: invalid number"),
+        ("DATE", "DATE.csv:2: invalid timestamp"),
+        (
+            "OLD_DATE",
+            "OLD_DATE.csv:3: invalid timestamp: 1969-12-31 is before 1970",
+        ),
+        ("OLD_TIME", "OLD_TIME.csv:3: invalid timestamp"),
+        ("OLD_RFC3339", "OLD_RFC3339.csv:3: invalid timestamp"),
+    ] {
+        let error = history(&provider, symbol, 0, i64::from(u32::MAX)).unwrap_err();
+
+        assert!(
+            matches!(error.downcast_ref(), Some(DataError::Parse(parse)) if parse.contains(message)),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn symbols_cannot_leave_the_directory() {
+    let directory = directory("escape");
+    let data = directory.join("data");
+
+    std::fs::create_dir_all(&data).unwrap();
+    std::fs::write(
+        directory.join("SECRET.csv"),
+        "timestamp,open,high,low,close\n2024-01-02,1,2,0.5,1.5\n",
+    )
+    .unwrap();
+
+    let provider = CsvProvider::new(data.clone(), CsvColumns::default(), None);
+
+    for symbol in ["../SECRET", "..\\SECRET", "", ".."] {
+        let error = history(&provider, symbol, 0, i64::from(u32::MAX)).unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<DataError>(),
+            Some(&DataError::NotFound),
+            "{:?}",
+            symbol
+        );
+    }
+
+    // The same file inside the directory is found.
+    std::fs::copy(directory.join("SECRET.csv"), data.join("SECRET.csv")).unwrap();
+
+    assert_eq!(
+        history(&provider, "SECRET", 0, i64::from(u32::MAX))
+            .unwrap()
+            .quotes
+            .len(),
+        1
+    );
+}
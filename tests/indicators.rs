@@ -0,0 +1,203 @@
+use market_view::indicators::{adx, atr, bollinger, ema, macd, rsi, sma, wma};
+use yahoo_finance_api::Quote;
+
+fn values(closes: &[f64]) -> Vec<Option<f64>> {
+    closes.iter().copied().map(Some).collect()
+}
+
+fn quote(high: f64, low: f64, close: f64) -> Quote {
+    Quote {
+        timestamp: 0,
+        open: close,
+        high,
+        low,
+        volume: 100,
+        close,
+        adjclose: close,
+    }
+}
+
+/// Compares up to `tolerance`, `None`s must line up.
+fn assert_series(actual: &[Option<f64>], expected: &[Option<f64>], tolerance: f64) {
+    assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+
+    for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+        match (actual, expected) {
+            (Some(actual), Some(expected)) => assert!(
+                (actual - expected).abs() <= tolerance,
+                "{} is not {} at {}",
+                actual,
+                expected,
+                index
+            ),
+            (None, None) => {}
+            _ => panic!("{:?} is not {:?} at {}", actual, expected, index),
+        }
+    }
+}
+
+#[test]
+fn moving_averages_match_hand_computed_values() {
+    let closes = values(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+
+    assert_series(
+        &sma(&closes, 3),
+        &[None, None, Some(2.0), Some(3.0), Some(4.0)],
+        1e-12,
+    );
+
+    // Weights 1, 2 and 3 over 6.
+    assert_series(
+        &wma(&closes, 3),
+        &[
+            None,
+            None,
+            Some(14.0 / 6.0),
+            Some(20.0 / 6.0),
+            Some(26.0 / 6.0),
+        ],
+        1e-12,
+    );
+
+    // Seeded with the SMA, then halfway to each new close.
+    let closes = values(&[1.0, 2.0, 4.0, 8.0, 16.0]);
+
+    assert_series(
+        &ema(&closes, 3),
+        &[
+            None,
+            None,
+            Some(7.0 / 3.0),
+            Some(31.0 / 6.0),
+            Some(127.0 / 12.0),
+        ],
+        1e-12,
+    );
+
+    let (middle, upper, lower) = bollinger(&values(&[1.0, 2.0, 3.0]), 3, 2.0);
+    let deviation = (2.0_f64 / 3.0).sqrt();
+
+    assert_series(&middle, &[None, None, Some(2.0)], 1e-12);
+    assert_series(&upper, &[None, None, Some(2.0 + 2.0 * deviation)], 1e-12);
+    assert_series(&lower, &[None, None, Some(2.0 - 2.0 * deviation)], 1e-12);
+}
+
+#[test]
+fn rsi_uses_wilder_smoothing() {
+    // Gains average 2/3 and losses 1/6 over the first three changes, then
+    // each change adds a third of itself to two thirds of the averages.
+    let closes = values(&[10.0, 11.0, 10.5, 11.5, 11.0, 12.0]);
+
+    assert_series(
+        &rsi(&closes, 3),
+        &[
+            None,
+            None,
+            None,
+            Some(80.0),
+            Some(100.0 - 100.0 / 2.6),
+            Some(100.0 - 100.0 / 4.4),
+        ],
+        1e-9,
+    );
+
+    // Flat and rising series have no losses.
+    assert_series(
+        &rsi(&values(&[1.0, 2.0, 3.0]), 2),
+        &[None, None, Some(100.0)],
+        0.0,
+    );
+}
+
+#[test]
+fn rsi_matches_the_wilder_reference_series() {
+    // The 14 day example from Wilder's "New Concepts in Technical Trading
+    // Systems" as tabulated by StockCharts, which rounds to two decimals.
+    let closes = values(&[
+        44.3389, 44.0902, 44.1497, 43.6124, 44.2778, 44.8264, 45.0955, 45.4245, 45.8433, 46.0826,
+        45.8931, 46.0328, 45.6140, 46.2820, 46.2820, 46.0028, 46.0328, 46.4116, 46.2222, 45.6439,
+        46.2122, 46.2521, 45.7137, 46.4515, 45.7835, 45.3548, 44.0288, 44.1783, 44.2181, 44.5672,
+        43.4205, 42.6628, 43.1314,
+    ]);
+
+    let expected = [
+        70.53, 66.32, 66.55, 69.41, 66.36, 57.97, 62.93, 63.26, 56.06, 62.38, 54.71, 50.42, 39.99,
+        41.46, 41.87, 45.46, 37.30, 33.08, 37.77,
+    ];
+
+    let expected = std::iter::repeat_n(None, 14)
+        .chain(expected.into_iter().map(Some))
+        .collect::<Vec<_>>();
+
+    assert_series(&rsi(&closes, 14), &expected, 0.01);
+}
+
+#[test]
+fn macd_is_the_difference_of_two_emas() {
+    let closes = values(&[1.0, 2.0, 4.0, 8.0, 16.0]);
+
+    // EMA(2) is 3/2, 19/6, 115/18 and 691/54, EMA(3) is 7/3, 31/6 and 127/12.
+    let (line, signal, histogram) = macd(&closes, 2, 3, 2);
+
+    assert_series(
+        &line,
+        &[
+            None,
+            None,
+            Some(5.0 / 6.0),
+            Some(11.0 / 9.0),
+            Some(239.0 / 108.0),
+        ],
+        1e-12,
+    );
+    assert_series(
+        &signal,
+        &[None, None, None, Some(37.0 / 36.0), Some(589.0 / 324.0)],
+        1e-12,
+    );
+    assert_series(
+        &histogram,
+        &[None, None, None, Some(7.0 / 36.0), Some(32.0 / 81.0)],
+        1e-12,
+    );
+}
+
+#[test]
+fn adx_and_atr_follow_the_directional_movement() {
+    let quotes = [
+        quote(10.0, 8.0, 9.0),
+        quote(11.0, 9.0, 10.0),
+        quote(12.0, 10.0, 11.0),
+        quote(11.0, 8.0, 9.0),
+        quote(10.0, 7.0, 8.0),
+    ];
+
+    // True ranges 2, 2, 2, 3, 3.
+    assert_series(
+        &atr(&quotes, 2),
+        &[None, Some(2.0), Some(2.0), Some(2.5), Some(2.75)],
+        1e-12,
+    );
+
+    // +DM 1, 1, 0, 0 and -DM 0, 0, 2, 1 from the second bar on, smoothed
+    // over the ranges from the second bar.
+    let (adx, plus, minus) = adx(&quotes, 2);
+
+    assert_series(
+        &plus,
+        &[None, None, Some(50.0), Some(20.0), Some(100.0 / 11.0)],
+        1e-9,
+    );
+    assert_series(
+        &minus,
+        &[None, None, Some(0.0), Some(40.0), Some(400.0 / 11.0)],
+        1e-9,
+    );
+
+    // DX is 100, 100/3 and 60.
+    assert_series(
+        &adx,
+        &[None, None, None, Some(200.0 / 3.0), Some(190.0 / 3.0)],
+        1e-9,
+    );
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use market_view::portfolio::{CostBasis, Ledger, Transaction, TransactionKind};
+use yahoo_finance_api::Quote;
+
+fn quotes(closes: &[f64]) -> HashMap<String, Vec<Quote>> {
+    let quotes = closes
+        .iter()
+        .enumerate()
+        .map(|(index, close)| Quote {
+            timestamp: index as u64 + 1,
+            open: *close,
+            high: *close,
+            low: *close,
+            volume: 0,
+            close: *close,
+            adjclose: *close,
+        })
+        .collect();
+
+    [("TEST".to_string(), quotes)].into()
+}
+
+fn ledger(cost_basis: CostBasis, transactions: &[(u64, TransactionKind, f64)]) -> Ledger {
+    let mut ledger = Ledger::default();
+
+    ledger.cost_basis = cost_basis;
+
+    for (timestamp, kind, commission) in transactions {
+        ledger.add(Transaction {
+            timestamp: *timestamp,
+            symbol: "TEST".to_string(),
+            kind: *kind,
+            commission: *commission,
+        });
+    }
+
+    ledger
+}
+
+fn values(curve: &[[f64; 2]]) -> Vec<f64> {
+    curve.iter().map(|[_, value]| *value).collect()
+}
+
+#[test]
+fn cost_basis_decides_the_realized_gain() {
+    let transactions = [
+        (
+            1,
+            TransactionKind::Buy {
+                quantity: 10.0,
+                price: 100.0,
+            },
+            0.0,
+        ),
+        (
+            2,
+            TransactionKind::Buy {
+                quantity: 10.0,
+                price: 120.0,
+            },
+            0.0,
+        ),
+        (
+            3,
+            TransactionKind::Sell {
+                quantity: 15.0,
+                price: 130.0,
+            },
+            0.0,
+        ),
+    ];
+
+    // The 10 shares at 100 and 5 of the ones at 120 are sold.
+    let fifo = &ledger(CostBasis::Fifo, &transactions).positions()[0];
+
+    assert_eq!(fifo.quantity, 5.0);
+    assert_eq!(fifo.cost, 600.0);
+    assert_eq!(fifo.realized, 350.0);
+
+    // Every share cost 110.
+    let average = &ledger(CostBasis::Average, &transactions).positions()[0];
+
+    assert_eq!(average.quantity, 5.0);
+    assert_eq!(average.cost, 550.0);
+    assert_eq!(average.realized, 300.0);
+}
+
+#[test]
+fn selling_at_the_close_keeps_the_equity_flat() {
+    let ledger = ledger(
+        CostBasis::Fifo,
+        &[
+            (
+                1,
+                TransactionKind::Buy {
+                    quantity: 10.0,
+                    price: 100.0,
+                },
+                0.0,
+            ),
+            (
+                3,
+                TransactionKind::Sell {
+                    quantity: 10.0,
+                    price: 100.0,
+                },
+                0.0,
+            ),
+        ],
+    );
+
+    let curve = ledger.equity_curve(&quotes(&[100.0, 100.0, 100.0, 100.0]));
+
+    assert_eq!(curve[0][0], 1.0);
+    assert_eq!(values(&curve), [1000.0; 4]);
+}
+
+#[test]
+fn equity_counts_cash_dividends_and_fees() {
+    let ledger = ledger(
+        CostBasis::Fifo,
+        &[
+            // 1001 deposited for the shares and the commission.
+            (
+                1,
+                TransactionKind::Buy {
+                    quantity: 10.0,
+                    price: 100.0,
+                },
+                1.0,
+            ),
+            (2, TransactionKind::Dividend { amount: 5.0 }, 0.0),
+            (
+                3,
+                TransactionKind::Sell {
+                    quantity: 10.0,
+                    price: 120.0,
+                },
+                1.0,
+            ),
+            (4, TransactionKind::Fee { amount: 4.0 }, 0.0),
+        ],
+    );
+
+    let curve = ledger.equity_curve(&quotes(&[100.0, 110.0, 120.0, 125.0]));
+
+    assert_eq!(values(&curve), [1000.0, 1105.0, 1204.0, 1200.0]);
+}
@@ -0,0 +1,286 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use market_view::{
+    providers::{FakeProvider, History, MarketDataProvider},
+    requests::{get_history, Outcome, RequestLayer, RequestLog, RequestPolicy},
+    DataError, Interval,
+};
+use yahoo_finance_api::{time::OffsetDateTime, Quote, YQuoteItem, YahooError};
+
+/// Fails the first `failures` history requests with `error`, answers after `delay`.
+struct ScriptedProvider {
+    calls: Arc<AtomicUsize>,
+    failures: usize,
+    error: fn() -> YahooError,
+    delay: Duration,
+    fake: FakeProvider,
+}
+
+impl ScriptedProvider {
+    fn new(failures: usize, error: fn() -> YahooError, delay: Duration) -> Self {
+        ScriptedProvider {
+            calls: Arc::default(),
+            failures,
+            error,
+            delay,
+            fake: FakeProvider::default(),
+        }
+    }
+}
+
+impl MarketDataProvider for ScriptedProvider {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<YQuoteItem>>> {
+        self.fake.search(query)
+    }
+
+    fn history<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> BoxFuture<'a, anyhow::Result<History>> {
+        Box::pin(async move {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+
+            tokio::time::sleep(self.delay).await;
+
+            if call < self.failures {
+                return Err((self.error)().into());
+            }
+
+            self.fake.history(symbol, start, end, interval).await
+        })
+    }
+
+    fn latest_quote<'a>(&'a self, symbol: &'a str) -> BoxFuture<'a, anyhow::Result<Quote>> {
+        self.fake.latest_quote(symbol)
+    }
+}
+
+fn policy() -> RequestPolicy {
+    RequestPolicy {
+        rate: 1000.0,
+        burst: 1000.0,
+        retries: 3,
+        backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+        timeout: Duration::from_secs(5),
+    }
+}
+
+fn layer(provider: ScriptedProvider, policy: RequestPolicy) -> (RequestLayer, RequestLog) {
+    let log = RequestLog::default();
+
+    (
+        RequestLayer::new(Box::new(provider), policy, log.clone()),
+        log,
+    )
+}
+
+async fn history(layer: &RequestLayer) -> anyhow::Result<History> {
+    let end = OffsetDateTime::now_utc();
+
+    layer
+        .history(
+            "FAKE",
+            end - yahoo_finance_api::time::Duration::days(10),
+            end,
+            Interval::OneDay,
+        )
+        .await
+}
+
+#[tokio::test]
+async fn transient_errors_are_retried() {
+    let provider = ScriptedProvider::new(
+        2,
+        || YahooError::FetchFailed("503 Service Unavailable".to_string()),
+        Duration::ZERO,
+    );
+    let calls = provider.calls.clone();
+    let (layer, log) = layer(provider, policy());
+
+    assert!(!history(&layer).await.unwrap().quotes.is_empty());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    let records = log.records();
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].attempts, 3);
+    assert_eq!(records[0].outcome, Outcome::Succeeded);
+}
+
+#[tokio::test]
+async fn permanent_errors_are_not_retried() {
+    let provider = ScriptedProvider::new(1, || YahooError::EmptyDataSet, Duration::ZERO);
+    let calls = provider.calls.clone();
+    let (layer, log) = layer(provider, policy());
+
+    assert!(history(&layer).await.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(matches!(log.records()[0].outcome, Outcome::Failed(_)));
+}
+
+#[tokio::test]
+async fn retries_stop_after_the_limit() {
+    let provider = ScriptedProvider::new(
+        10,
+        || YahooError::FetchFailed("429 Too Many Requests".to_string()),
+        Duration::ZERO,
+    );
+    let calls = provider.calls.clone();
+    let (layer, log) = layer(provider, policy());
+
+    assert!(history(&layer).await.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+    assert_eq!(log.records()[0].attempts, 4);
+}
+
+#[tokio::test]
+async fn slow_requests_time_out() {
+    let provider = ScriptedProvider::new(0, || YahooError::EmptyDataSet, Duration::from_secs(1));
+    let calls = provider.calls.clone();
+    let (layer, log) = layer(
+        provider,
+        RequestPolicy {
+            retries: 1,
+            timeout: Duration::from_millis(20),
+            ..policy()
+        },
+    );
+
+    assert!(history(&layer).await.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(log.records()[0].outcome, Outcome::TimedOut);
+}
+
+#[tokio::test]
+async fn identical_requests_in_flight_are_joined() {
+    let provider =
+        ScriptedProvider::new(0, || YahooError::EmptyDataSet, Duration::from_millis(100));
+    let calls = provider.calls.clone();
+    let (layer, log) = layer(provider, policy());
+
+    let (first, second) = tokio::join!(history(&layer), history(&layer));
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(first.unwrap().quotes.len(), second.unwrap().quotes.len());
+
+    let mut outcomes = log
+        .records()
+        .into_iter()
+        .map(|record| record.outcome)
+        .collect::<Vec<_>>();
+
+    outcomes.sort_by_key(|outcome| *outcome == Outcome::Joined);
+
+    assert_eq!(outcomes, [Outcome::Succeeded, Outcome::Joined]);
+
+    history(&layer).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn abandoned_requests_are_not_joined() {
+    let provider =
+        ScriptedProvider::new(0, || YahooError::EmptyDataSet, Duration::from_millis(100));
+    let calls = provider.calls.clone();
+    let (layer, _) = layer(provider, policy());
+
+    // The caller gives up while the request is in flight.
+    assert!(
+        tokio::time::timeout(Duration::from_millis(20), history(&layer))
+            .await
+            .is_err()
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    history(&layer).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    history(&layer).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn requests_are_rate_limited() {
+    let provider = ScriptedProvider::new(0, || YahooError::EmptyDataSet, Duration::ZERO);
+    let (layer, _) = layer(
+        provider,
+        RequestPolicy {
+            rate: 20.0,
+            burst: 1.0,
+            ..policy()
+        },
+    );
+
+    let started = Instant::now();
+
+    for _ in 0..5 {
+        layer.search("FAKE").await.unwrap();
+    }
+
+    assert!(started.elapsed() >= Duration::from_millis(190));
+}
+
+#[tokio::test]
+async fn errors_are_classified() {
+    let classify = |error: YahooError| DataError::from(anyhow::Error::from(error));
+
+    assert_eq!(
+        classify(YahooError::FetchFailed("404 Not Found".to_string())),
+        DataError::NotFound
+    );
+    assert_eq!(
+        classify(YahooError::FetchFailed("429 Too Many Requests".to_string())),
+        DataError::RateLimited
+    );
+    assert!(matches!(
+        classify(YahooError::FetchFailed("502 Bad Gateway".to_string())),
+        DataError::Network(_)
+    ));
+    assert!(matches!(
+        classify(YahooError::InvalidJson),
+        DataError::Parse(_)
+    ));
+    assert_eq!(classify(YahooError::EmptyDataSet), DataError::NoDataInRange);
+
+    // Classification survives the request layer, also for joined requests.
+    let provider = ScriptedProvider::new(
+        1,
+        || YahooError::FetchFailed("404 Not Found".to_string()),
+        Duration::from_millis(50),
+    );
+    let (layer, _) = layer(provider, policy());
+
+    let (first, second) = tokio::join!(history(&layer), history(&layer));
+
+    assert_eq!(DataError::from(first.err().unwrap()), DataError::NotFound);
+    assert_eq!(DataError::from(second.err().unwrap()), DataError::NotFound);
+
+    let empty = get_history(
+        &layer,
+        "FAKE",
+        OffsetDateTime::now_utc() - yahoo_finance_api::time::Duration::days(10),
+        OffsetDateTime::now_utc() - yahoo_finance_api::time::Duration::days(20),
+        Interval::OneDay,
+    )
+    .await;
+
+    assert_eq!(empty.err(), Some(DataError::NoDataInRange));
+}
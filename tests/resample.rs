@@ -0,0 +1,283 @@
+use chrono::{FixedOffset, NaiveDate};
+use market_view::resample::{heikin_ashi, resample, Bucket};
+use yahoo_finance_api::Quote;
+
+const UTC: FixedOffset = FixedOffset::east_opt(0).unwrap();
+
+fn quote(timestamp: u64, open: f64, high: f64, low: f64, close: f64) -> Quote {
+    Quote {
+        timestamp,
+        open,
+        high,
+        low,
+        volume: 100,
+        close,
+        adjclose: close,
+    }
+}
+
+#[test]
+fn heikin_ashi_bars_smooth_the_series() {
+    let bars = heikin_ashi(&[
+        quote(1, 10.0, 14.0, 9.0, 12.0),
+        quote(2, 12.0, 13.0, 6.0, 7.0),
+    ]);
+
+    assert_eq!(bars.len(), 2);
+
+    assert_eq!(bars[0].open, 11.0);
+    assert_eq!(bars[0].close, 11.25);
+    assert_eq!((bars[0].high, bars[0].low), (14.0, 9.0));
+
+    // Opens in the middle of the previous body, the high covers that open.
+    assert_eq!(bars[1].open, 11.125);
+    assert_eq!(bars[1].close, 9.5);
+    assert_eq!((bars[1].high, bars[1].low), (13.0, 6.0));
+    assert_eq!((bars[1].timestamp, bars[1].volume), (2, 100));
+
+    assert!(heikin_ashi(&[]).is_empty());
+}
+
+/// Unix seconds of a UTC date and time.
+fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> u64 {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp() as u64
+}
+
+/// One bar per timestamp with the close counting up from 1.
+fn bars(timestamps: &[u64]) -> Vec<Quote> {
+    timestamps
+        .iter()
+        .enumerate()
+        .map(|(index, timestamp)| {
+            let close = index as f64 + 1.0;
+
+            quote(*timestamp, close, close, close, close)
+        })
+        .collect()
+}
+
+/// Timestamps and closes of the resampled bars.
+fn buckets(timestamps: &[u64], bucket: Bucket, utc_offset: FixedOffset) -> Vec<(u64, f64)> {
+    resample(&bars(timestamps), bucket, utc_offset)
+        .iter()
+        .map(|quote| (quote.timestamp, quote.close))
+        .collect()
+}
+
+#[test]
+fn buckets_aggregate_their_bars() {
+    let quotes = [
+        quote(at(2024, 1, 2, 14, 30), 10.0, 12.0, 9.0, 11.0),
+        quote(at(2024, 1, 3, 14, 30), 11.0, 15.0, 10.0, 14.0),
+        quote(at(2024, 1, 4, 14, 30), 14.0, 14.0, 8.0, 9.0),
+    ];
+
+    let weekly = resample(&quotes, Bucket::Weeks(1), UTC);
+
+    assert_eq!(weekly.len(), 1);
+    assert_eq!(weekly[0].timestamp, quotes[0].timestamp);
+    assert_eq!(
+        (
+            weekly[0].open,
+            weekly[0].high,
+            weekly[0].low,
+            weekly[0].close
+        ),
+        (10.0, 15.0, 8.0, 9.0)
+    );
+    assert_eq!(weekly[0].volume, 300);
+}
+
+#[test]
+fn weeks_start_on_monday() {
+    // Friday 2024-01-05, Sunday the 7th, Monday the 8th and Sunday the 14th.
+    let timestamps = [
+        at(2024, 1, 5, 14, 30),
+        at(2024, 1, 7, 23, 59),
+        at(2024, 1, 8, 0, 0),
+        at(2024, 1, 14, 12, 0),
+    ];
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Weeks(1), UTC),
+        [(timestamps[0], 2.0), (timestamps[2], 4.0)]
+    );
+}
+
+#[test]
+fn months_and_quarters_follow_the_calendar() {
+    let timestamps = [
+        at(2024, 1, 31, 14, 30),
+        at(2024, 2, 1, 14, 30),
+        at(2024, 3, 31, 14, 30),
+        at(2024, 4, 1, 14, 30),
+        at(2024, 12, 31, 14, 30),
+        at(2025, 1, 2, 14, 30),
+    ];
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Months(1), UTC),
+        [
+            (timestamps[0], 1.0),
+            (timestamps[1], 2.0),
+            (timestamps[2], 3.0),
+            (timestamps[3], 4.0),
+            (timestamps[4], 5.0),
+            (timestamps[5], 6.0),
+        ]
+    );
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Quarters(1), UTC),
+        [
+            (timestamps[0], 3.0),
+            (timestamps[3], 4.0),
+            (timestamps[4], 5.0),
+            (timestamps[5], 6.0),
+        ]
+    );
+}
+
+#[test]
+fn minutes_split_on_round_times() {
+    let timestamps = [
+        at(2024, 1, 2, 14, 30),
+        at(2024, 1, 2, 14, 40),
+        at(2024, 1, 2, 14, 45),
+        at(2024, 1, 2, 14, 55),
+        at(2024, 1, 2, 15, 0),
+    ];
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Minutes(15), UTC),
+        [
+            (timestamps[0], 2.0),
+            (timestamps[2], 4.0),
+            (timestamps[4], 5.0)
+        ]
+    );
+}
+
+#[test]
+fn boundaries_are_in_the_exchange_time_zone() {
+    let timestamps = [
+        at(2024, 1, 7, 12, 0),
+        // Sunday evening in UTC, Monday morning in Tokyo.
+        at(2024, 1, 7, 23, 0),
+        // Monday in UTC, still Sunday evening in New York.
+        at(2024, 1, 8, 3, 0),
+    ];
+
+    let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+    let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Weeks(1), UTC),
+        [(timestamps[0], 2.0), (timestamps[2], 3.0)]
+    );
+    assert_eq!(
+        buckets(&timestamps, Bucket::Weeks(1), tokyo),
+        [(timestamps[0], 1.0), (timestamps[1], 3.0)]
+    );
+    assert_eq!(
+        buckets(&timestamps, Bucket::Weeks(1), new_york),
+        [(timestamps[0], 3.0)]
+    );
+
+    // The last day of January in New York is February in UTC.
+    let timestamps = [at(2024, 1, 31, 12, 0), at(2024, 2, 1, 2, 0)];
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Months(1), new_york),
+        [(timestamps[0], 2.0)]
+    );
+    assert_eq!(buckets(&timestamps, Bucket::Months(1), UTC).len(), 2);
+}
+
+#[test]
+fn hours_split_on_round_hours() {
+    let timestamps = [
+        at(2024, 1, 2, 13, 30),
+        at(2024, 1, 2, 13, 59),
+        at(2024, 1, 2, 14, 0),
+        at(2024, 1, 2, 15, 30),
+        at(2024, 1, 2, 16, 0),
+    ];
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Hours(1), UTC),
+        [
+            (timestamps[0], 2.0),
+            (timestamps[2], 3.0),
+            (timestamps[3], 4.0),
+            (timestamps[4], 5.0),
+        ]
+    );
+
+    // Two hour buckets start on even hours.
+    assert_eq!(
+        buckets(&timestamps, Bucket::Hours(2), UTC),
+        [
+            (timestamps[0], 2.0),
+            (timestamps[2], 4.0),
+            (timestamps[4], 5.0)
+        ]
+    );
+
+    // Kolkata is half an hour off, 14:00 UTC is 19:30 there.
+    let kolkata = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Hours(1), kolkata),
+        [(timestamps[0], 3.0), (timestamps[3], 5.0)]
+    );
+}
+
+#[test]
+fn days_split_at_local_midnight() {
+    let timestamps = [
+        at(2024, 1, 1, 14, 30),
+        at(2024, 1, 1, 23, 59),
+        at(2024, 1, 2, 0, 0),
+        at(2024, 1, 3, 14, 30),
+        at(2024, 1, 4, 14, 30),
+    ];
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Days(1), UTC),
+        [
+            (timestamps[0], 2.0),
+            (timestamps[2], 3.0),
+            (timestamps[3], 4.0),
+            (timestamps[4], 5.0),
+        ]
+    );
+
+    // Two day buckets count from 1970-01-01, 2024-01-01 ends one.
+    assert_eq!(
+        buckets(&timestamps, Bucket::Days(2), UTC),
+        [
+            (timestamps[0], 2.0),
+            (timestamps[2], 4.0),
+            (timestamps[4], 5.0)
+        ]
+    );
+
+    // 23:59 UTC is already the next day in Tokyo.
+    let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+
+    assert_eq!(
+        buckets(&timestamps, Bucket::Days(1), tokyo),
+        [
+            (timestamps[0], 1.0),
+            (timestamps[1], 3.0),
+            (timestamps[3], 4.0),
+            (timestamps[4], 5.0),
+        ]
+    );
+}
@@ -0,0 +1,120 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use market_view::{
+    alerts::AlertBook,
+    providers::FakeProvider,
+    scheduler::{RefreshSettings, Scheduler, TradingHours},
+    storage::QuotesStorage,
+    Interval, Storage,
+};
+
+/// New York in winter.
+const UTC_OFFSET: i32 = -5 * 60 * 60;
+
+fn time(rfc3339: &str) -> SystemTime {
+    let seconds = chrono::DateTime::parse_from_rfc3339(rfc3339)
+        .unwrap()
+        .timestamp();
+
+    UNIX_EPOCH + Duration::from_secs(seconds as u64)
+}
+
+#[test]
+fn trading_hours_follow_the_exchange_time_zone() {
+    let hours = TradingHours::default();
+
+    assert!(hours.is_open(time("2024-01-03T10:00:00-05:00"), UTC_OFFSET));
+    assert!(!hours.is_open(time("2024-01-03T09:00:00-05:00"), UTC_OFFSET));
+    assert!(!hours.is_open(time("2024-01-03T16:00:00-05:00"), UTC_OFFSET));
+    assert!(!hours.is_open(time("2024-01-06T12:00:00-05:00"), UTC_OFFSET));
+    assert!(hours.is_open(time("2024-01-03T12:00:00-05:00"), UTC_OFFSET));
+    assert!(!hours.is_open(time("2024-01-03T12:00:00-05:00"), 0));
+}
+
+#[test]
+fn last_close_skips_weekends() {
+    let hours = TradingHours::default();
+
+    assert_eq!(
+        hours.last_close(time("2024-01-03T17:00:00-05:00"), UTC_OFFSET),
+        Some(time("2024-01-03T16:00:00-05:00"))
+    );
+    assert_eq!(
+        hours.last_close(time("2024-01-03T12:00:00-05:00"), UTC_OFFSET),
+        Some(time("2024-01-02T16:00:00-05:00"))
+    );
+    assert_eq!(
+        hours.last_close(time("2024-01-08T09:00:00-05:00"), UTC_OFFSET),
+        Some(time("2024-01-05T16:00:00-05:00"))
+    );
+}
+
+#[test]
+fn scheduler_refreshes_targets_by_their_source() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let storage = Storage::new(
+        Arc::new(FakeProvider::default()),
+        runtime.handle().clone(),
+        QuotesStorage::default(),
+        AlertBook::default(),
+    );
+
+    let scheduler = Scheduler::new(
+        storage.clone(),
+        RefreshSettings {
+            pause_outside_hours: false,
+            ..RefreshSettings::default()
+        },
+    );
+
+    scheduler.set_targets([
+        ("FAKE".to_string(), Interval::OneWeek),
+        ("FAKE".to_string(), Interval::OneDay),
+        ("TEST".to_string(), Interval::FifteenMinutes),
+    ]);
+    scheduler.start();
+
+    let queue = scheduler.queue();
+
+    assert_eq!(
+        queue
+            .iter()
+            .map(|entry| (entry.symbol.as_str(), entry.interval))
+            .collect::<Vec<_>>(),
+        [("FAKE", Interval::OneDay), ("TEST", Interval::FiveMinutes)]
+    );
+
+    let started = Instant::now();
+
+    while scheduler
+        .queue()
+        .iter()
+        .any(|entry| entry.last_run.is_none())
+    {
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let snapshot = storage.snapshot();
+
+    assert!(snapshot.quotes("FAKE", Interval::OneDay).is_some());
+    assert!(snapshot.quotes("TEST", Interval::FiveMinutes).is_some());
+
+    for entry in scheduler.queue() {
+        assert!(entry.fetched);
+        assert!(entry.next_run > entry.last_run);
+    }
+
+    scheduler.set_targets([("FAKE".to_string(), Interval::OneDay)]);
+
+    assert_eq!(scheduler.queue().len(), 1);
+}
@@ -0,0 +1,335 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::future::BoxFuture;
+use market_view::{
+    alerts::{AlertBook, Condition},
+    providers::{FakeProvider, History, MarketDataProvider},
+    requests,
+    storage::{merge_quotes, QuotesStorage},
+    DataError, Interval, Storage,
+};
+use yahoo_finance_api::{time::OffsetDateTime, Quote, YQuoteItem, YahooError};
+
+const DAY: u64 = 86_400;
+const DAY_LENGTH: Duration = Duration::from_secs(DAY);
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn storage(runtime: &tokio::runtime::Runtime) -> Storage {
+    Storage::new(
+        Arc::new(FakeProvider::default()),
+        runtime.handle().clone(),
+        QuotesStorage::default(),
+        AlertBook::default(),
+    )
+}
+
+/// Fetches `symbol` through the storage and returns the `interval` series.
+fn fetch(storage: &Storage, symbol: &str, interval: Interval) -> Vec<Quote> {
+    let (source, _) = interval.source();
+
+    storage
+        .runtime()
+        .block_on(storage.update(symbol, source, Duration::ZERO))
+        .unwrap();
+
+    storage
+        .snapshot()
+        .series(symbol, interval)
+        .unwrap_or_default()
+}
+
+fn quote(timestamp: u64, close: f64) -> Quote {
+    Quote {
+        timestamp,
+        open: close,
+        high: close,
+        low: close,
+        volume: 1,
+        close,
+        adjclose: close,
+    }
+}
+
+/// Holds every history request until the gate is opened.
+struct GatedProvider {
+    gate: tokio::sync::Semaphore,
+    fake: FakeProvider,
+}
+
+impl MarketDataProvider for GatedProvider {
+    fn name(&self) -> &str {
+        "gated"
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<YQuoteItem>>> {
+        self.fake.search(query)
+    }
+
+    fn history<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> BoxFuture<'a, anyhow::Result<History>> {
+        Box::pin(async move {
+            let _permit = self.gate.acquire().await?;
+
+            self.fake.history(symbol, start, end, interval).await
+        })
+    }
+
+    fn latest_quote<'a>(&'a self, _symbol: &'a str) -> BoxFuture<'a, anyhow::Result<Quote>> {
+        Box::pin(async move {
+            Err(YahooError::FetchFailed("503 Service Unavailable".to_string()).into())
+        })
+    }
+}
+
+/// Answers history requests with `404 Not Found` until `failures` runs out.
+struct FailingProvider {
+    failures: AtomicUsize,
+    fake: FakeProvider,
+}
+
+impl MarketDataProvider for FailingProvider {
+    fn name(&self) -> &str {
+        "failing"
+    }
+
+    fn search<'a>(&'a self, _query: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<YQuoteItem>>> {
+        Box::pin(
+            async move { Err(YahooError::FetchFailed("429 Too Many Requests".to_string()).into()) },
+        )
+    }
+
+    fn history<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+        interval: Interval,
+    ) -> BoxFuture<'a, anyhow::Result<History>> {
+        Box::pin(async move {
+            if self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |failures| {
+                    failures.checked_sub(1)
+                })
+                .is_ok()
+            {
+                return Err(YahooError::FetchFailed("404 Not Found".to_string()).into());
+            }
+
+            self.fake.history(symbol, start, end, interval).await
+        })
+    }
+
+    fn latest_quote<'a>(&'a self, _symbol: &'a str) -> BoxFuture<'a, anyhow::Result<Quote>> {
+        Box::pin(async move {
+            Err(YahooError::FetchFailed("503 Service Unavailable".to_string()).into())
+        })
+    }
+}
+
+#[test]
+fn search_goes_through_the_provider() {
+    let runtime = runtime();
+    let storage = storage(&runtime);
+
+    let results = runtime
+        .block_on(requests::search(storage.provider().as_ref(), "fa"))
+        .unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].symbol, "FAKE");
+}
+
+#[test]
+fn update_fills_the_storage() {
+    let runtime = runtime();
+    let storage = storage(&runtime);
+
+    let quotes = fetch(&storage, "FAKE", Interval::OneDay);
+
+    assert!(quotes.len() > 100);
+    assert!(quotes
+        .windows(2)
+        .all(|pair| pair[1].timestamp - pair[0].timestamp == DAY));
+    assert_eq!(storage.get_quotes("FAKE", Interval::OneDay), Some(quotes));
+    assert_eq!(storage.get_quotes("TEST", Interval::OneDay), None);
+}
+
+#[test]
+fn storage_is_usable_while_a_fetch_is_in_flight() {
+    let runtime = runtime();
+    let provider = Arc::new(GatedProvider {
+        gate: tokio::sync::Semaphore::new(0),
+        fake: FakeProvider::default(),
+    });
+
+    let storage = Storage::new(
+        provider.clone(),
+        runtime.handle().clone(),
+        QuotesStorage::default(),
+        AlertBook::default(),
+    );
+
+    let fetch = runtime.spawn({
+        let storage = storage.clone();
+
+        async move {
+            storage
+                .update("FAKE", Interval::OneDay, Duration::ZERO)
+                .await
+        }
+    });
+
+    // Give the fetch time to reach the provider.
+    std::thread::sleep(Duration::from_millis(100));
+
+    storage.modify_alerts(|alerts| {
+        alerts.add(
+            "FAKE",
+            Interval::OneDay,
+            Condition::PercentMove { percent: 50.0 },
+        );
+    });
+
+    let before = storage.snapshot();
+
+    assert_eq!(before.alerts.alerts.len(), 1);
+    assert!(before.quotes("FAKE", Interval::OneDay).is_none());
+    assert!(!runtime
+        .block_on(storage.update("FAKE", Interval::OneDay, Duration::MAX))
+        .unwrap());
+
+    provider.gate.add_permits(1);
+
+    assert!(runtime.block_on(fetch).unwrap().unwrap());
+
+    let after = storage.snapshot();
+
+    assert!(after.version > before.version);
+    assert!(!after.quotes("FAKE", Interval::OneDay).unwrap().is_empty());
+    assert!(before.quotes("FAKE", Interval::OneDay).is_none());
+}
+
+#[test]
+fn subscribers_are_notified_of_new_bars() {
+    let runtime = runtime();
+    let storage = storage(&runtime);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    let mut daily = storage.subscribe("FAKE", Interval::OneDay, move || {
+        sender.send(()).unwrap();
+    });
+    let mut hourly = storage.subscribe("FAKE", Interval::OneHour, || {});
+
+    assert!(!daily.changed());
+
+    fetch(&storage, "FAKE", Interval::OneDay);
+
+    receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+    assert!(daily.changed());
+    assert!(!daily.changed());
+    assert!(!hourly.changed());
+
+    drop(daily);
+
+    fetch(&storage, "FAKE", Interval::OneDay);
+
+    assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+}
+
+#[test]
+fn failed_updates_are_reported_until_the_series_loads() {
+    let runtime = runtime();
+    let storage = Storage::new(
+        Arc::new(FailingProvider {
+            failures: AtomicUsize::new(1),
+            fake: FakeProvider::default(),
+        }),
+        runtime.handle().clone(),
+        QuotesStorage::default(),
+        AlertBook::default(),
+    );
+
+    let update = || runtime.block_on(storage.update("FAKE", Interval::OneDay, DAY_LENGTH));
+
+    assert_eq!(update(), Err(DataError::NotFound));
+    assert_eq!(
+        storage.snapshot().error("FAKE", Interval::OneWeek),
+        Some(&DataError::NotFound)
+    );
+    assert_eq!(
+        runtime.block_on(storage.search("fa")).unwrap_err(),
+        DataError::RateLimited
+    );
+    assert_eq!(
+        runtime.block_on(storage.latest_quote("FAKE")).unwrap_err(),
+        DataError::Network("503 Service Unavailable".to_string())
+    );
+
+    let errors = storage.errors().records();
+
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors[0].context, "FAKE 1d");
+    assert_eq!(errors[2].context, "Latest quote of FAKE");
+
+    // The failed attempt does not count as an update, the retry loads everything.
+    assert_eq!(update(), Ok(true));
+    assert!(storage.snapshot().error("FAKE", Interval::OneDay).is_none());
+    assert!(storage.get_quotes("FAKE", Interval::OneDay).unwrap().len() > 100);
+}
+
+#[test]
+fn weekly_series_is_resampled_from_daily_bars() {
+    let runtime = runtime();
+    let storage = storage(&runtime);
+
+    let daily = fetch(&storage, "FAKE", Interval::OneDay);
+    let weekly = fetch(&storage, "FAKE", Interval::OneWeek);
+
+    assert!(weekly.len() >= daily.len() / 7);
+    assert!(weekly.len() <= daily.len() / 7 + 2);
+    assert_eq!(weekly.last().unwrap().close, daily.last().unwrap().close);
+    assert_eq!(
+        weekly.iter().map(|quote| quote.volume).sum::<u64>(),
+        daily.iter().map(|quote| quote.volume).sum::<u64>()
+    );
+}
+
+#[test]
+fn merge_replaces_the_forming_bar() {
+    let mut history = vec![quote(0, 1.0), quote(DAY, 2.0), quote(2 * DAY + 100, 3.0)];
+
+    merge_quotes(
+        &mut history,
+        vec![quote(2 * DAY, 4.0), quote(3 * DAY, 5.0)],
+        DAY,
+    );
+
+    assert_eq!(
+        history
+            .iter()
+            .map(|quote| (quote.timestamp, quote.close))
+            .collect::<Vec<_>>(),
+        [(0, 1.0), (DAY, 2.0), (2 * DAY, 4.0), (3 * DAY, 5.0)]
+    );
+}